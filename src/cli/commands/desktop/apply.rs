@@ -1,10 +1,12 @@
 use clap::ArgMatches;
 use log::{error, warn};
 
+use crate::cli::commands::desktop::deps;
 use crate::cli::commands::utils;
 use crate::cli::completions;
 use crate::core::{
     config::{DesktopConfig, GlobalConfig},
+    dependency::Dependency,
     desktop::Desktop,
     theme::{Theme, ThemeFile},
 };
@@ -17,6 +19,23 @@ pub fn run(matches: &ArgMatches) {
         None => return,
     };
 
+    // Installing the desktop wipes the current one, so it must be able to run first
+    if !matches.is_present("ignore-deps") {
+        let missing = Dependency::get_missing(&current_desktop);
+        if !missing.is_empty() {
+            let missing: Vec<_> = missing
+                .into_iter()
+                .map(|dependency| (dependency, false))
+                .collect();
+            deps::print_table(&missing);
+            error!(
+                "Desktop |{}| has missing dependencies. Install them or use |--ignore-deps|",
+                current_desktop.get_name()
+            );
+            return;
+        }
+    }
+
     let mut global_config = GlobalConfig::new();
     let previous_desktop = match global_config.get_current_desktop() {
         Some(d) => Some(d.to_desktop()),
@@ -52,20 +71,23 @@ pub fn run(matches: &ArgMatches) {
     );
 
     let dry_run = matches.is_present("dry-run");
-    if !dry_run {
-        *global_config.get_mut_current_desktop() = Some(current_desktop.clone());
-        *global_config.get_mut_current_theme() = Some(default_theme.clone());
-        global_config.save();
-        completions::generate_completions()
-    }
-
-    current_desktop.to_desktop().apply(
+    let installed = current_desktop.to_desktop().apply(
         &previous_desktop,
         &default_theme.to_theme(),
         &actived,
         &inverted,
         dry_run,
     );
+    if !installed {
+        return;
+    }
+
+    if !dry_run {
+        *global_config.get_mut_current_desktop() = Some(current_desktop.clone());
+        *global_config.get_mut_current_theme() = Some(default_theme.clone());
+        global_config.save();
+        completions::generate_completions()
+    }
 
     if previous_desktop.is_none() {
         warn!("|Reboot your computer to see the changes!|")
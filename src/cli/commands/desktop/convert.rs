@@ -0,0 +1,35 @@
+use clap::ArgMatches;
+use colored::*;
+use log::error;
+
+use crate::cli::commands::utils;
+use crate::core::desktop::{Manifest, MANIFEST_FILE};
+
+pub fn run(matches: &ArgMatches) {
+    let desktop = match utils::get_desktop(matches.value_of("desktop")) {
+        Some(d) => d,
+        None => return,
+    };
+    if Manifest::exists(&desktop) {
+        error!(
+            "Desktop |{}| already has a |{}| manifest",
+            desktop.get_name(),
+            MANIFEST_FILE
+        );
+        return;
+    }
+
+    let manifest = Manifest::from_legacy(&desktop);
+    if matches.is_present("dry-run") {
+        print!("{}", manifest);
+        return;
+    }
+    if manifest.save(&desktop) {
+        println!(
+            "{} Desktop {} is now declared in {}, legacy files are no longer read",
+            "•".green(),
+            desktop.get_name().bold(),
+            MANIFEST_FILE.bold()
+        );
+    }
+}
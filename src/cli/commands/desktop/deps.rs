@@ -1,7 +1,10 @@
 use clap::ArgMatches;
+use colored::*;
+use log::{info, warn};
 
 use crate::cli::commands::utils;
 use crate::core::config::DesktopInfo;
+use crate::core::dependency::{Dependency, PackageManager, Resolver};
 
 pub fn run(matches: &ArgMatches) {
     let desktop = match utils::get_desktop(matches.value_of("desktop")) {
@@ -10,14 +13,65 @@ pub fn run(matches: &ArgMatches) {
     };
 
     let desktop_info = DesktopInfo::new(&desktop);
-
-    for dep in desktop_info.get_dependencies() {
-        println!("{}", dep);
+    let dependencies = Dependency::get_dependencies(&desktop_info, matches.is_present("all"));
+    let resolver = Resolver::new(&desktop.to_desktop());
+    let resolved = resolver.resolve(dependencies);
+    if !matches.is_present("install-cmd") {
+        print_table(&resolved);
+        return;
     }
 
-    if matches.is_present("all") {
-        for dep in desktop_info.get_optional_dependencies() {
-            println!("{}", dep);
+    // Only printed, installing packages is up to the user
+    let manager = match PackageManager::detect() {
+        Some(manager) => manager,
+        None => return,
+    };
+    let missing: Vec<Dependency> = resolved
+        .into_iter()
+        .filter(|(_, present)| !present)
+        .map(|(dependency, _)| dependency)
+        .collect();
+    if missing.is_empty() {
+        info!("Every dependency of |{}| is present", desktop.get_name());
+        return;
+    }
+    for name in desktop_info.get_packages().keys() {
+        if PackageManager::from(name).is_none() {
+            warn!("Unknown package manager |{}| in desktop info", name);
         }
     }
+    if let Some(command) = manager.get_install_command(&missing, desktop_info.get_packages()) {
+        println!("{}", command);
+    }
+}
+
+pub fn print_table(dependencies: &[(Dependency, bool)]) {
+    let width = dependencies
+        .iter()
+        .map(|(dependency, _)| dependency.get_name().len())
+        .max()
+        .unwrap_or(0);
+    for (dependency, present) in dependencies {
+        let status = if *present {
+            "present".green()
+        } else if dependency.is_optional() {
+            "missing".yellow()
+        } else {
+            "missing".red()
+        };
+        let optional = if dependency.is_optional() {
+            " (optional)"
+        } else {
+            ""
+        };
+        println!(
+            "{} {:<width$}  {:<4}  {}{}",
+            "•".green(),
+            dependency.get_name(),
+            dependency.get_kind().to_string(),
+            status,
+            optional,
+            width = width
+        );
+    }
 }
@@ -2,12 +2,14 @@ use clap::ArgMatches;
 
 pub mod add;
 pub mod apply;
+pub mod convert;
 pub mod deps;
 pub mod edit;
 pub mod info;
 pub mod list;
 pub mod newskeleton;
 pub mod remove;
+pub mod restorebackup;
 pub mod setdefault;
 pub mod status;
 
@@ -23,6 +25,8 @@ pub fn handle_subcommands(sub_matches: &ArgMatches) {
         Some(("remove", sub_sub_matches)) => remove::run(sub_sub_matches),
         Some(("set-default-theme", sub_sub_matches)) => setdefault::run(sub_sub_matches),
         Some(("apply", sub_sub_matches)) => apply::run(sub_sub_matches),
+        Some(("convert", sub_sub_matches)) => convert::run(sub_sub_matches),
+        Some(("restore-backup", sub_sub_matches)) => restorebackup::run(sub_sub_matches),
         _ =>  unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 }
@@ -0,0 +1,62 @@
+use clap::ArgMatches;
+use colored::*;
+use log::{error, info};
+
+use crate::core::backup::Backup;
+use crate::core::config::GlobalConfig;
+
+pub fn run(matches: &ArgMatches) {
+    let backups = Backup::get_backups();
+    if backups.is_empty() {
+        println!("There are no backups");
+        return;
+    }
+
+    if matches.is_present("list") {
+        for backup in &backups {
+            println!(
+                "{} {} (installing {})",
+                "•".green(),
+                backup.get_date().bold(),
+                backup.get_desktop().bold()
+            );
+            for path in backup.get_paths() {
+                println!("    {}", path);
+            }
+        }
+        return;
+    }
+
+    let dry_run = matches.is_present("dry-run");
+    let mut global_config = GlobalConfig::new();
+    if let Some(current_desktop) = global_config.get_current_desktop() {
+        info!("Uninstalling desktop |{}|...", current_desktop.get_name());
+        if !dry_run {
+            current_desktop.to_desktop().clean_files();
+        }
+    }
+
+    let restored = match Backup::restore_all(dry_run) {
+        Some(restored) => restored,
+        None => {
+            error!("Some files could not be restored, backups were kept");
+            return;
+        }
+    };
+    if dry_run {
+        for path in &restored {
+            println!("{} {}", "•".green(), path);
+        }
+        return;
+    }
+
+    // Restored files belong to the user again, so the next desktop backs them up
+    *global_config.get_mut_current_desktop() = None;
+    *global_config.get_mut_current_theme() = None;
+    global_config.save();
+    println!(
+        "{} Restored {} files, no desktop is installed now",
+        "•".green(),
+        restored.len().to_string().bold()
+    );
+}
@@ -4,6 +4,8 @@ use colored::*;
 use crate::cli::commands;
 use crate::cli::commands::utils;
 use crate::core::config::{DesktopConfig, GlobalConfig};
+use crate::core::desktop::{get_link_state, Desktop, LinkState, Lockfile};
+use std::path::Path;
 
 pub fn run(matches: &ArgMatches) {
     println!("");
@@ -39,8 +41,58 @@ pub fn run(matches: &ArgMatches) {
             println!("{} {}", "• Current theme:".yellow().bold(), current_theme)
         }
     }
+    if let Some(lockfile) = Lockfile::read() {
+        if lockfile.get_desktop() == desktop.get_name() {
+            print_installed_files(&lockfile, &desktop.to_desktop());
+        }
+    }
     println!("");
 
     commands::pattern::list::run(matches);
     commands::extra::list::run(matches);
 }
+
+fn print_installed_files(lockfile: &Lockfile, desktop: &Desktop) {
+    if lockfile.get_links().is_empty() {
+        println!(
+            "{} {} files on {}",
+            "• Installed:".yellow().bold(),
+            lockfile.get_files().len(),
+            lockfile.get_date()
+        );
+    } else {
+        println!(
+            "{} {} links on {}",
+            "• Installed:".yellow().bold(),
+            lockfile.get_links().len(),
+            lockfile.get_date()
+        );
+    }
+    let unhealthy = lockfile
+        .get_links()
+        .iter()
+        .map(|(target, source)| (target, get_link_state(Path::new(source), Path::new(target))))
+        .filter(|(_, state)| *state != LinkState::Linked)
+        .collect::<Vec<_>>();
+    if !unhealthy.is_empty() {
+        println!("{}", "• Links needing attention:".red().bold());
+        for (target, state) in unhealthy {
+            println!("    {} ({})", target, state);
+        }
+    }
+    let modified = lockfile.get_modified();
+    if !modified.is_empty() {
+        println!("{}", "• Modified since install:".red().bold());
+        for path in modified {
+            println!("    {}", path);
+        }
+    }
+    // Files a previous version of the desktop installed, removed on the next uninstall
+    let orphans = lockfile.get_orphans(desktop);
+    if !orphans.is_empty() {
+        println!("{}", "• No longer provided by the desktop:".red().bold());
+        for path in orphans {
+            println!("    {}", path);
+        }
+    }
+}
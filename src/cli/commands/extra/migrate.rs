@@ -0,0 +1,37 @@
+use clap::ArgMatches;
+use colored::*;
+
+use crate::cli::commands::utils;
+use crate::core::postscript::PostScript;
+
+pub fn run(matches: &ArgMatches) {
+    let desktop = match utils::get_desktop(matches.value_of("desktop")) {
+        Some(d) => d,
+        None => return,
+    };
+    let dry_run = matches.is_present("dry-run");
+
+    let migrated = match PostScript::migrate_extras(&desktop, dry_run) {
+        Some(migrated) => migrated,
+        None => return,
+    };
+    if migrated.is_empty() {
+        println!(
+            "{} No theme has arguments for extras of desktop {}",
+            "•".green(),
+            desktop.get_name().bold()
+        );
+        return;
+    }
+    for (extra, themes) in &migrated {
+        println!(
+            "{} {}: {}",
+            "•".green(),
+            extra.bold().cyan(),
+            themes.join(", ")
+        );
+    }
+    if dry_run {
+        println!("\nRun without {} to move them", "--dry-run".bold());
+    }
+}
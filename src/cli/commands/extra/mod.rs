@@ -5,6 +5,7 @@ use crate::cli::commands::utils::Action;
 mod edit;
 pub mod list;
 mod manage;
+mod migrate;
 
 pub fn handle_subcommands(sub_matches: &ArgMatches) {
     match sub_matches.subcommand() {
@@ -13,6 +14,7 @@ pub fn handle_subcommands(sub_matches: &ArgMatches) {
         Some(("enable", sub_sub_matches)) => manage::run(sub_sub_matches, Action::Enable),
         Some(("disable", sub_sub_matches)) => manage::run(sub_sub_matches, Action::Disable),
         Some(("toggle", sub_sub_matches)) => manage::run(sub_sub_matches, Action::Toggle),
+        Some(("migrate", sub_sub_matches)) => migrate::run(sub_sub_matches),
         _ =>  unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 }
@@ -0,0 +1,38 @@
+use clap::ArgMatches;
+use colored::*;
+
+use crate::core::history::History;
+
+pub fn run(matches: &ArgMatches) {
+    let entries = History::get_entries();
+
+    if matches.is_present("quiet") {
+        entries
+            .iter()
+            .for_each(|entry| println!("{}", entry.get_id()));
+        return;
+    }
+
+    println!();
+    println!("{}\n", "HISTORY".bold().underline().blue());
+
+    if entries.is_empty() {
+        println!("There are no themes applied yet\n");
+        return;
+    }
+
+    let last_idx = entries.len() - 1;
+    for (idx, entry) in entries.iter().enumerate() {
+        let current = if idx == last_idx { " (Current)" } else { "" };
+        println!(
+            "{} {}  {}  {:<20} {}{}",
+            "•".blue(),
+            entry.get_id().bold(),
+            entry.get_date(),
+            entry.get_theme(),
+            format!("({})", entry.get_desktop()).cyan(),
+            current.green()
+        );
+    }
+    println!();
+}
@@ -0,0 +1,14 @@
+use clap::ArgMatches;
+
+mod list;
+mod restore;
+pub mod undo;
+
+pub fn handle_subcommands(sub_matches: &ArgMatches) {
+    match sub_matches.subcommand() {
+        Some(("restore", sub_sub_matches)) => restore::run(sub_sub_matches),
+        // Without subcommand, history is listed
+        None => list::run(sub_matches),
+        _ => unreachable!("Exhausted list of subcommands"),
+    }
+}
@@ -0,0 +1,12 @@
+use clap::ArgMatches;
+
+use crate::core::{config::GlobalConfig, history::History};
+
+pub fn run(matches: &ArgMatches) {
+    let id = matches.value_of("id").unwrap();
+
+    let mut global_config = GlobalConfig::new();
+    if History::restore(id, &mut global_config) {
+        global_config.save();
+    }
+}
@@ -0,0 +1,10 @@
+use clap::ArgMatches;
+
+use crate::core::{config::GlobalConfig, history::History};
+
+pub fn run(_matches: &ArgMatches) {
+    let mut global_config = GlobalConfig::new();
+    if History::undo(&mut global_config) {
+        global_config.save();
+    }
+}
@@ -4,9 +4,11 @@ mod config;
 mod desktop;
 mod extra;
 mod fav;
+mod history;
 mod pattern;
 mod theme;
 mod utils;
+mod watch;
 
 pub fn handle_command(matches: &ArgMatches) {
     match matches.subcommand() {
@@ -14,8 +16,11 @@ pub fn handle_command(matches: &ArgMatches) {
         Some(("desktop", sub_matches)) => desktop::handle_subcommands(sub_matches),
         Some(("extra", sub_matches)) => extra::handle_subcommands(sub_matches),
         Some(("fav", sub_matches)) => fav::handle_subcommands(sub_matches),
+        Some(("history", sub_matches)) => history::handle_subcommands(sub_matches),
         Some(("pattern", sub_matches)) => pattern::handle_subcommands(sub_matches),
         Some(("theme", sub_matches)) => theme::handle_subcommands(sub_matches),
+        Some(("undo", sub_matches)) => history::undo::run(sub_matches),
+        Some(("watch", sub_matches)) => watch::run(sub_matches),
         _ =>  unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 }
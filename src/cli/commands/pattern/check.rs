@@ -0,0 +1,58 @@
+use clap::ArgMatches;
+use colored::*;
+use log::error;
+use std::process;
+
+use crate::cli::commands::utils;
+use crate::core::{
+    config::{GlobalConfig, UserConfig},
+    diagnostic,
+    pattern::Pattern,
+    theme::Theme,
+};
+
+pub fn run(matches: &ArgMatches) {
+    let desktop = match utils::get_desktop(matches.value_of("desktop")) {
+        Some(d) => d,
+        None => process::exit(1),
+    };
+
+    let theme = match matches.value_of("theme") {
+        Some(theme) => Theme::get_by_name(theme),
+        None => {
+            let global_config = GlobalConfig::new();
+            let current_theme = global_config.get_current_theme().clone();
+            if current_theme.is_none() {
+                error!("|There is no theme installed!| Try with -t option instead");
+            }
+            current_theme
+        }
+    };
+    let theme = match theme {
+        Some(t) => t.to_theme(),
+        None => process::exit(1),
+    };
+
+    let patterns = Pattern::get_patterns(&desktop);
+    let diagnostics = Pattern::check(&patterns, &theme, &UserConfig::new());
+    diagnostics.iter().for_each(|diagnostic| diagnostic.log());
+
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    if diagnostic::has_errors(&diagnostics) {
+        println!(
+            "\n{} problems found in {} patterns of desktop {} with theme {}",
+            errors.to_string().red().bold(),
+            patterns.len(),
+            desktop.get_name().bold(),
+            theme.get_name().bold()
+        );
+        process::exit(1);
+    }
+    println!(
+        "{} All {} patterns of desktop {} are valid with theme {}",
+        "•".green(),
+        patterns.len(),
+        desktop.get_name().bold(),
+        theme.get_name().bold()
+    );
+}
@@ -2,6 +2,7 @@ use clap::ArgMatches;
 
 use crate::cli::commands::utils::Action;
 
+mod check;
 mod edit;
 mod invert;
 pub mod list;
@@ -15,6 +16,7 @@ pub fn handle_subcommands(sub_matches: &ArgMatches) {
         Some(("disable", sub_sub_matches)) => manage::run(sub_sub_matches, Action::Disable),
         Some(("toggle", sub_sub_matches)) => manage::run(sub_sub_matches, Action::Toggle),
         Some(("invert", sub_sub_matches)) => invert::run(sub_sub_matches),
+        Some(("check", sub_sub_matches)) => check::run(sub_sub_matches),
         _ =>  unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 }
@@ -1,10 +1,10 @@
-use clap::ArgMatches;
+use clap::{ArgMatches, Values};
 use log::error;
 
 use crate::cli::commands::utils;
 use crate::core::{
     config::{DesktopConfig, GlobalConfig},
-    theme::Theme,
+    theme::{Theme, ThemeFile},
 };
 
 pub fn run(matches: &ArgMatches) {
@@ -15,6 +15,26 @@ pub fn run(matches: &ArgMatches) {
         None => return,
     };
 
+    apply(
+        theme,
+        matches.values_of("pattern"),
+        matches.values_of("invert"),
+        matches.is_present("dry-run"),
+        matches.is_present("strict"),
+        matches.is_present("force"),
+    );
+}
+
+// Applies the theme on the current desktop. It becomes the current theme unless only
+// some patterns were selected
+pub fn apply(
+    theme: ThemeFile,
+    patterns: Option<Values>,
+    invert: Option<Values>,
+    dry_run: bool,
+    strict: bool,
+    force: bool,
+) {
     let mut global_config = GlobalConfig::new();
 
     let current_desktop = match global_config.get_current_desktop() {
@@ -26,25 +46,21 @@ pub fn run(matches: &ArgMatches) {
     };
     let desktop_config = DesktopConfig::new(current_desktop);
 
-    let actived = utils::get_actived(
-        matches.values_of("pattern"),
-        current_desktop,
-        &desktop_config,
-    );
+    let selected_patterns = patterns.is_some();
+    let actived = utils::get_actived(patterns, current_desktop, &desktop_config);
 
-    let inverted = utils::get_inverted(
-        matches.values_of("invert"),
-        current_desktop,
-        &desktop_config,
-    );
+    let inverted = utils::get_inverted(invert, current_desktop, &desktop_config);
 
-    let dry_run = matches.is_present("dry-run");
-
-    current_desktop
-        .to_desktop()
-        .apply_theme(&theme.to_theme(), &actived, &inverted, dry_run);
+    let is_applied = current_desktop.to_desktop().apply_theme(
+        &theme.to_theme(),
+        &actived,
+        &inverted,
+        dry_run,
+        strict,
+        force,
+    );
 
-    if !dry_run && !matches.is_present("pattern") {
+    if is_applied && !dry_run && !selected_patterns {
         *global_config.get_mut_current_theme() = Some(theme);
         global_config.save()
     }
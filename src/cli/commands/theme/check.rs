@@ -0,0 +1,44 @@
+use clap::ArgMatches;
+use colored::*;
+use std::process;
+
+use crate::core::{config::GlobalConfig, diagnostic, theme::Theme};
+
+pub fn run(matches: &ArgMatches) {
+    let themes = match matches.values_of("theme") {
+        Some(names) => names.filter_map(Theme::get_by_name).collect(),
+        None => Theme::get_themes(),
+    };
+
+    // Extras are checked against the current desktop
+    let global_config = GlobalConfig::new();
+    let desktop = global_config
+        .get_current_desktop()
+        .as_ref()
+        .map(|desktop| desktop.to_desktop());
+
+    let mut broken_themes = 0;
+    for theme in &themes {
+        let diagnostics = theme.check(desktop.as_ref());
+        diagnostics.iter().for_each(|diagnostic| diagnostic.log());
+
+        let status = if diagnostic::has_errors(&diagnostics) {
+            broken_themes += 1;
+            "ERROR".red().bold()
+        } else if !diagnostics.is_empty() {
+            "WARNING".yellow().bold()
+        } else {
+            "OK".green().bold()
+        };
+        println!("{} {} {}", "•".cyan(), theme.get_name().bold(), status);
+    }
+
+    if broken_themes > 0 {
+        println!(
+            "\n{} of {} themes have errors",
+            broken_themes.to_string().red().bold(),
+            themes.len()
+        );
+        process::exit(1);
+    }
+}
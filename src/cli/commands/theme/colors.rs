@@ -27,18 +27,42 @@ pub fn run(matches: &ArgMatches) {
     let theme = theme_file.to_theme();
     let sorted_colors = theme.get_colors().into_iter().collect::<BTreeSet<_>>();
 
+    let mut details = Vec::new();
+    if let Some(parent) = theme.get_inherits() {
+        details.push(format!("inherits {}", parent));
+    }
+    if let Some(light) = theme.get_light() {
+        details.push(format!("light {}", light));
+    }
+    if let Some(dark) = theme.get_dark() {
+        details.push(format!("dark {}", dark));
+    }
+    let inherits = if details.is_empty() {
+        String::new()
+    } else {
+        format!("({})", details.join(", "))
+    };
     println!(
-        "\n{} {}\n",
+        "\n{} {} {}\n",
         "THEME".bold().underline().green(),
-        theme.get_name().bold()
+        theme.get_name().bold(),
+        inherits.dimmed()
     );
     for (color_key, color_value) in sorted_colors {
         let hex_color = format!("#{}", &color_value);
+        // Inherited colors show the ancestor that defines them
+        let origin = theme.get_color_origin(color_key);
+        let origin = if origin != theme.get_name() {
+            format!("(from {})", origin)
+        } else {
+            String::new()
+        };
         match utils::hex_to_rgb(&hex_color) {
             Some((r, g, b)) => println!(
-                "{hex_color}  {}  {}",
+                "{hex_color}  {}  {} {}",
                 "██".truecolor(r, g, b),
-                color_key.bold().cyan()
+                color_key.bold().cyan(),
+                origin.dimmed()
             ),
             None => error!(
                 "Invalid hexadcimal color '|{}|' in property |{}|",
@@ -46,5 +70,58 @@ pub fn run(matches: &ArgMatches) {
             ),
         }
     }
+
+    println!("\n{}\n", "CONTRAST".bold().underline().yellow());
+    for check in theme.get_contrast() {
+        let get_color = |key: &str| utils::hex_to_rgb(&format!("#{}", theme.get_colors()[key]));
+        let (fg, bg) = (
+            get_color(check.get_foreground()),
+            get_color(check.get_background()),
+        );
+        let sample = match (fg, bg) {
+            (Some((fr, fg, fb)), Some((br, bg, bb))) => {
+                " Aa ".truecolor(fr, fg, fb).on_truecolor(br, bg, bb)
+            }
+            _ => "    ".normal(),
+        };
+        let grade = format!("{:<8}", check.get_grade());
+        let grade = if check.passes() {
+            grade.green()
+        } else {
+            grade.red().bold()
+        };
+        let inverted = if check.is_inverted() {
+            "(inverted)"
+        } else {
+            ""
+        };
+        println!(
+            "{:>5.2}  {}  {} {} on {} {}",
+            check.get_ratio(),
+            sample,
+            grade,
+            check.get_foreground().bold().cyan(),
+            check.get_background().bold().cyan(),
+            inverted.dimmed()
+        );
+    }
+
+    if !theme.get_extras().is_empty() {
+        println!("\n{}\n", "EXTRAS".bold().underline().magenta());
+    }
+    for (extra, args) in theme.get_extras() {
+        let origin = theme.get_extra_origin(extra);
+        let origin = if origin != theme.get_name() {
+            format!("(from {})", origin)
+        } else {
+            String::new()
+        };
+        println!(
+            "{} {} {}",
+            extra.bold().cyan(),
+            args.join(" "),
+            origin.dimmed()
+        );
+    }
     println!();
 }
@@ -0,0 +1,35 @@
+use clap::ArgMatches;
+use log::{error, info};
+use std::fs;
+
+use crate::core::{self, theme::Theme};
+
+pub fn run(matches: &ArgMatches) {
+    let theme_name = matches.value_of("theme").unwrap();
+    let format = matches.value_of("format").unwrap();
+
+    let theme = match Theme::get_by_name(theme_name) {
+        Some(t) => t.to_theme(),
+        None => return,
+    };
+    let content = match theme.export(format) {
+        Some(content) => content,
+        None => return,
+    };
+
+    match matches.value_of("output") {
+        Some(output) => {
+            let output = core::expand_path(output);
+            match fs::write(&output, content) {
+                Ok(_) => info!(
+                    "Exported theme |{}| as |{}| to |{}|",
+                    theme.get_name(),
+                    format,
+                    output
+                ),
+                Err(e) => error!("Could not write |{}|: |{}|", output, e),
+            }
+        }
+        None => print!("{}", content),
+    }
+}
@@ -0,0 +1,44 @@
+use clap::ArgMatches;
+use colored::*;
+use log::error;
+
+use crate::core::theme::Theme;
+
+pub fn run(matches: &ArgMatches) {
+    let theme_name = matches.value_of("theme").unwrap();
+    let dry_run = matches.is_present("dry-run");
+    let target = match matches.value_of("target").map(str::parse::<f64>) {
+        Some(Ok(target)) if (1.0..=21.0).contains(&target) => Some(target),
+        Some(_) => {
+            error!("Target ratio must be a number between |1| and |21|");
+            return;
+        }
+        None => None,
+    };
+
+    let theme = match Theme::get_by_name(theme_name) {
+        Some(t) => t.to_theme(),
+        None => return,
+    };
+    let fixed = match theme.fix_contrast(target, dry_run) {
+        Some(fixed) => fixed,
+        None => return,
+    };
+    if fixed.is_empty() {
+        println!(
+            "{} All colors of theme {} have enough contrast",
+            "•".green(),
+            theme.get_name().bold()
+        );
+        return;
+    }
+    for (key, value) in &fixed {
+        println!(
+            "{} {}: {} -> {}",
+            "•".yellow(),
+            key.bold().cyan(),
+            theme.get_colors()[key],
+            value
+        );
+    }
+}
@@ -0,0 +1,12 @@
+use clap::ArgMatches;
+
+use crate::cli::completions;
+use crate::core::theme::{Mode, Theme};
+
+pub fn run(matches: &ArgMatches) {
+    let image = matches.value_of("image").unwrap();
+    let mode = Mode::from(matches.value_of("mode").unwrap()).unwrap();
+    if Theme::generate(image, matches.value_of("name"), mode) {
+        completions::generate_completions()
+    }
+}
@@ -0,0 +1,12 @@
+use clap::ArgMatches;
+
+use crate::cli::completions;
+use crate::core::theme::Theme;
+
+pub fn run(matches: &ArgMatches) {
+    let file = matches.value_of("file").unwrap();
+    let imported = Theme::import(file, matches.value_of("name"), matches.value_of("format"));
+    if imported {
+        completions::generate_completions()
+    }
+}
@@ -1,11 +1,17 @@
 use clap::ArgMatches;
 
 mod apply;
+mod check;
 mod colors;
 mod edit;
+mod export;
+mod fixcontrast;
+mod generate;
+mod import;
 mod list;
 mod newskeleton;
 mod remove;
+mod togglemode;
 
 pub fn handle_subcommands(sub_matches: &ArgMatches) {
     match sub_matches.subcommand() {
@@ -13,8 +19,14 @@ pub fn handle_subcommands(sub_matches: &ArgMatches) {
         Some(("colors", sub_sub_matches)) => colors::run(sub_sub_matches),
         Some(("edit", sub_sub_matches)) => edit::run(sub_sub_matches),
         Some(("new-skeleton", sub_sub_matches)) => newskeleton::run(sub_sub_matches),
+        Some(("export", sub_sub_matches)) => export::run(sub_sub_matches),
+        Some(("generate", sub_sub_matches)) => generate::run(sub_sub_matches),
+        Some(("import", sub_sub_matches)) => import::run(sub_sub_matches),
+        Some(("fix-contrast", sub_sub_matches)) => fixcontrast::run(sub_sub_matches),
         Some(("remove", sub_sub_matches)) => remove::run(sub_sub_matches),
+        Some(("toggle-mode", sub_sub_matches)) => togglemode::run(sub_sub_matches),
         Some(("apply", sub_sub_matches)) => apply::run(sub_sub_matches),
+        Some(("check", sub_sub_matches)) => check::run(sub_sub_matches),
         _ =>  unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 }
@@ -0,0 +1,55 @@
+use clap::ArgMatches;
+use log::{error, info};
+
+use super::apply;
+use crate::core::{config::GlobalConfig, theme::Mode};
+
+pub fn run(matches: &ArgMatches) {
+    let global_config = GlobalConfig::new();
+    let theme = match global_config.get_current_theme() {
+        Some(t) => t.to_theme(),
+        None => {
+            error!("|There is no theme installed!|");
+            return;
+        }
+    };
+
+    let declaring = theme.get_declaring_theme();
+    let current_mode = theme.get_mode(&declaring);
+    let mode = match matches.value_of("mode") {
+        Some(mode) => Mode::from(mode).unwrap(),
+        None => current_mode.opposite(),
+    };
+    // Scheduled runs ask for the same mode many times, nothing to do then
+    if mode == current_mode {
+        info!("Current theme |{}| is already |{}|", theme.get_name(), mode);
+        return;
+    }
+
+    let counterpart = match theme.get_counterpart(mode, &declaring) {
+        Some(counterpart) => counterpart,
+        None => {
+            error!(
+                "Theme |{}| has no {} counterpart, add |{} = \"<theme>\"| to its file",
+                theme.get_name(),
+                mode,
+                mode
+            );
+            return;
+        }
+    };
+    info!(
+        "Switching from |{}| to |{}| theme |{}|",
+        theme.get_name(),
+        mode,
+        counterpart.get_name()
+    );
+    apply::apply(
+        counterpart,
+        None,
+        None,
+        matches.is_present("dry-run"),
+        matches.is_present("strict"),
+        false,
+    );
+}
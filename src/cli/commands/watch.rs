@@ -0,0 +1,122 @@
+use chrono::Local;
+use clap::ArgMatches;
+use colored::*;
+use log::error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::core::{
+    config::{DesktopConfig, GlobalConfig, UserConfig},
+    theme::{Theme, ThemeFile},
+    watch::{self, Watcher},
+};
+
+pub fn run(matches: &ArgMatches) {
+    let debounce = match matches.value_of("debounce").unwrap().parse::<u64>() {
+        Ok(debounce) => Duration::from_millis(debounce),
+        Err(_) => {
+            error!("Debounce must be a number of milliseconds");
+            return;
+        }
+    };
+
+    let global_config = GlobalConfig::new();
+    let desktop_file = match global_config.get_current_desktop() {
+        Some(d) => d,
+        None => {
+            error!("|There is no desktop installed!|");
+            return;
+        }
+    };
+    let theme_file = match global_config.get_current_theme() {
+        Some(t) => t,
+        None => {
+            error!("|There is no theme installed!|");
+            return;
+        }
+    };
+
+    // Patterns may live anywhere if the desktop has a manifest, so each one is watched
+    let pattern_paths: BTreeMap<String, PathBuf> = desktop_file
+        .to_desktop()
+        .get_patterns()
+        .iter()
+        .map(|pattern| {
+            (
+                pattern.get_name().clone(),
+                PathBuf::from(pattern.get_path()),
+            )
+        })
+        .collect();
+    let user_settings_path = PathBuf::from(UserConfig::new().get_path());
+    let get_paths = |theme_paths: &Vec<PathBuf>| {
+        let mut paths: Vec<PathBuf> = pattern_paths.values().cloned().collect();
+        paths.extend(theme_paths.iter().cloned());
+        paths.push(user_settings_path.clone());
+        paths
+    };
+    let mut theme_paths = get_theme_paths(theme_file);
+    let mut watcher = Watcher::new(get_paths(&theme_paths));
+    println!(
+        "Watching patterns of desktop {} and theme {}, press Ctrl+C to stop",
+        desktop_file.get_name().bold(),
+        theme_file.get_name().bold()
+    );
+
+    loop {
+        let changes = watcher.wait_changes(debounce);
+
+        // Read again on every change, patterns may have been enabled or added meanwhile
+        let desktop_config = DesktopConfig::new(desktop_file);
+        let actived = desktop_config.get_actived();
+        let is_actived = |name: &String| *actived.get(name).unwrap_or(&false);
+        let theme_changed = changes.iter().any(|path| theme_paths.contains(path));
+        // The theme may inherit from other themes now, the watched files follow its ancestors
+        theme_paths = get_theme_paths(theme_file);
+        watcher.set_paths(get_paths(&theme_paths));
+        let patterns: BTreeSet<String> = if theme_changed || changes.contains(&user_settings_path) {
+            actived
+                .keys()
+                .filter(|name| is_actived(name))
+                .cloned()
+                .collect()
+        } else {
+            changes
+                .iter()
+                .filter_map(|path| watch::get_pattern_name(&pattern_paths, path))
+                .filter(is_actived)
+                .collect()
+        };
+        if patterns.is_empty() {
+            continue;
+        }
+
+        let time = Local::now().format("%H:%M:%S").to_string();
+        let names = patterns.iter().cloned().collect::<Vec<_>>().join(", ");
+        let desktop = desktop_file.to_desktop();
+        // A failing render is only reported, the next change may fix it
+        if desktop.render_patterns(
+            &theme_file.to_theme(),
+            &patterns,
+            desktop_config.get_inverted(),
+        ) {
+            println!("{} {} Rendered {}", time.dimmed(), "•".green(), names);
+        } else {
+            println!("{} {} Could not render {}", time.dimmed(), "•".red(), names);
+        }
+    }
+}
+
+// Files of the theme and of every theme it inherits from
+fn get_theme_paths(theme_file: &ThemeFile) -> Vec<PathBuf> {
+    let theme = theme_file.to_theme();
+    let ancestors = theme
+        .get_ancestors()
+        .iter()
+        .filter_map(|name| Theme::get_by_name(name));
+    std::iter::once(theme_file.clone())
+        .chain(ancestors)
+        .map(|theme_file| PathBuf::from(theme_file.get_path()))
+        .collect()
+}
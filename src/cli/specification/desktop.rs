@@ -42,6 +42,9 @@ pub fn init<'a>(app: Command<'a>, desktops: &'a [&'a str], themes: &'a [&'a str]
 					.short('a')
 					.long("all")
 					.help("Include optional dependencies"),
+				Arg::new("install-cmd")
+					.long("install-cmd")
+					.help("Print the command installing the missing dependencies with the package manager of this distro, without running it"),
 			])
 		)
 		.subcommand(Command::new("add")
@@ -103,6 +106,35 @@ pub fn init<'a>(app: Command<'a>, desktops: &'a [&'a str], themes: &'a [&'a str]
 				.help("Show status of specified desktop")
 			)
 		)
+		.subcommand(Command::new("convert")
+			.about("Generate a desktop.toml manifest from the directory layout of a desktop, current desktop by default")
+			.args([
+				Arg::new("desktop")
+					.short('d')
+					.long("desktop")
+					.takes_value(true)
+					.possible_values(desktops)
+					.help("Convert specified desktop"),
+				Arg::new("dry-run")
+					.long("dry-run")
+					.takes_value(false)
+					.help("Print the manifest instead of writing it")
+			])
+		)
+		.subcommand(Command::new("restore-backup")
+			.about("Restore the files gtheme backed up before installing desktops, as they were before gtheme")
+			.args([
+				Arg::new("list")
+					.short('l')
+					.long("list")
+					.takes_value(false)
+					.help("List backups instead of restoring them"),
+				Arg::new("dry-run")
+					.long("dry-run")
+					.takes_value(false)
+					.help("Show what would be restored without changing any file")
+			])
+		)
 		.subcommand(Command::new("apply")
 			.alias("a")
 			.about("Apply specified desktop")
@@ -139,7 +171,12 @@ pub fn init<'a>(app: Command<'a>, desktops: &'a [&'a str], themes: &'a [&'a str]
 				Arg::new("dry-run")
 					.long("dry-run")
 					.takes_value(false)
-					.help("See possible errors when applying the desktop without changing disk files. It does not effectively change desktop")
+					.help("See possible errors when applying the desktop without changing disk files. It does not effectively change desktop"),
+
+				Arg::new("ignore-deps")
+					.long("ignore-deps")
+					.takes_value(false)
+					.help("Apply the desktop even if some of its required dependencies are missing")
 			])
 		)
 	);
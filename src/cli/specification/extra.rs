@@ -97,6 +97,22 @@ pub fn init<'a>(app: Command<'a>, extras: &'a [&'a str], desktops: &'a [&'a str]
                             .possible_values(desktops)
                             .help("Toggle extras in specified desktop"),
                     ]),
+            )
+            .subcommand(
+                Command::new("migrate")
+                    .about("Move extras arguments out of theme files into the storage of each extra")
+                    .args([
+                        Arg::new("desktop")
+                            .short('d')
+                            .long("desktop")
+                            .takes_value(true)
+                            .possible_values(desktops)
+                            .help("Migrate extras of specified desktop"),
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .takes_value(false)
+                            .help("Show what would be moved without changing any file"),
+                    ]),
             ),
     );
 
@@ -0,0 +1,31 @@
+use clap::{Arg, Command};
+
+pub fn init(app: Command) -> Command {
+    app.subcommand(
+        Command::new("history")
+            .alias("hi")
+            .about("List past theme applies, or restore one of them")
+            .arg(
+                Arg::new("quiet")
+                    .short('q')
+                    .long("quiet")
+                    .help("Show only history entry ids"),
+            )
+            .subcommand(
+                Command::new("restore")
+                    .alias("r")
+                    .about(
+                        "Go back to the state right after specified apply, undoing every later one",
+                    )
+                    .arg(
+                        Arg::new("id")
+                            .required(true)
+                            .takes_value(true)
+                            .help("History entry id to restore"),
+                    ),
+            ),
+    )
+    .subcommand(
+        Command::new("undo").about("Undo the last theme apply, restoring overwritten files"),
+    )
+}
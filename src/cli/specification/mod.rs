@@ -2,8 +2,10 @@ mod config;
 mod desktop;
 mod extra;
 mod fav;
+mod history;
 mod pattern;
 mod theme;
+mod watch;
 
 use clap::{Arg, Command};
 
@@ -30,9 +32,11 @@ pub fn create_app<'a>(
     app = config::init(app);
     app = desktop::init(app, desktops, themes);
     app = theme::init(app, themes, patterns);
-    app = pattern::init(app, patterns, desktops);
+    app = pattern::init(app, patterns, desktops, themes);
     app = extra::init(app, extras, desktops);
     app = fav::init(app, fav_themes, themes);
+    app = history::init(app);
+    app = watch::init(app);
 
     return app;
 }
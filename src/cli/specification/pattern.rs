@@ -1,6 +1,11 @@
 use clap::{Arg, Command};
 
-pub fn init<'a>(app: Command<'a>, patterns: &'a [&'a str], desktops: &'a [&'a str]) -> Command<'a> {
+pub fn init<'a>(
+    app: Command<'a>,
+    patterns: &'a [&'a str],
+    desktops: &'a [&'a str],
+    themes: &'a [&'a str],
+) -> Command<'a> {
     let app = app.subcommand(Command::new("pattern")
 		.alias("p")
 		.about("Manage patterns")
@@ -100,6 +105,23 @@ pub fn init<'a>(app: Command<'a>, patterns: &'a [&'a str], desktops: &'a [&'a st
 					.help("Toggle patterns in specified desktop")
 			])
 		)
+		.subcommand(Command::new("check")
+			.about("Check all patterns of current desktop against current theme by default")
+			.args([
+				Arg::new("theme")
+					.short('t')
+					.long("theme")
+					.takes_value(true)
+					.possible_values(themes)
+					.help("Check patterns against specified theme"),
+				Arg::new("desktop")
+					.short('d')
+					.long("desktop")
+					.takes_value(true)
+					.possible_values(desktops)
+					.help("Check patterns of specified desktop")
+			])
+		)
 		.subcommand(Command::new("invert")
 			.alias("i")
 			.about("Invert specified patterns or return them to default if they are already inverted")
@@ -1,5 +1,7 @@
 use clap::{Arg, Command};
 
+use crate::core::theme::{EXPORT_FORMATS, IMPORT_FORMATS};
+
 pub fn init<'a>(app: Command<'a>, themes: &'a [&'a str], patterns: &'a [&'a str]) -> Command<'a> {
     let app = app.subcommand(Command::new("theme")
 		.alias("t")
@@ -47,6 +49,90 @@ pub fn init<'a>(app: Command<'a>, themes: &'a [&'a str], patterns: &'a [&'a str]
 				.help("New theme name")
 			)
 		)
+		.subcommand(Command::new("import")
+			.about("Create a new theme from a base16/base24, Xresources, Alacritty, kitty or iTerm2 color scheme")
+			.args([
+				Arg::new("file")
+					.required(true)
+					.takes_value(true)
+					.help("Color scheme file to import"),
+				Arg::new("name")
+					.short('n')
+					.long("name")
+					.takes_value(true)
+					.help("New theme name, taken from the scheme or the file name by default"),
+				Arg::new("format")
+					.short('f')
+					.long("format")
+					.takes_value(true)
+					.possible_values(IMPORT_FORMATS)
+					.help("Format of the file, detected from its name and content by default")
+			])
+		)
+		.subcommand(Command::new("generate")
+			.about("Create a new theme from the dominant colors of an image")
+			.args([
+				Arg::new("image")
+					.required(true)
+					.takes_value(true)
+					.help("PNG or JPEG image, i.e a wallpaper"),
+				Arg::new("name")
+					.short('n')
+					.long("name")
+					.takes_value(true)
+					.help("New theme name, taken from the image file name by default"),
+				Arg::new("mode")
+					.short('m')
+					.long("mode")
+					.takes_value(true)
+					.possible_values(["dark", "light"])
+					.default_value("dark")
+					.help("Generate a dark or a light theme")
+			])
+		)
+		.subcommand(Command::new("export")
+			.about("Export a theme to the color scheme format of another program")
+			.args([
+				Arg::new("theme")
+					.required(true)
+					.takes_value(true)
+					.possible_values(themes)
+					.help("Theme to export"),
+				Arg::new("format")
+					.short('f')
+					.long("format")
+					.required(true)
+					.takes_value(true)
+					.possible_values(EXPORT_FORMATS)
+					.help("Format to export the theme to"),
+				Arg::new("output")
+					.short('o')
+					.long("output")
+					.takes_value(true)
+					.value_name("file")
+					.help("File to write the exported theme, standard output by default")
+			])
+		)
+		.subcommand(Command::new("fix-contrast")
+			.about("Adjust the lightness of low contrast colors of a theme")
+			.args([
+				Arg::new("theme")
+					.required(true)
+					.takes_value(true)
+					.possible_values(themes)
+					.help("Theme to fix"),
+				Arg::new("target")
+					.short('t')
+					.long("target")
+					.takes_value(true)
+					.value_name("ratio")
+					.help("Contrast ratio every pair must reach, 4.5 for text and 3 for terminal colors by default"),
+				Arg::new("dry-run")
+					.long("dry-run")
+					.takes_value(false)
+					.help("Show the fixed colors without changing the theme file")
+			])
+		)
 		.subcommand(Command::new("remove")
 			.about("Remove themes")
 			.arg(Arg::new("theme")
@@ -57,6 +143,35 @@ pub fn init<'a>(app: Command<'a>, themes: &'a [&'a str], patterns: &'a [&'a str]
 				.help("Themes to remove")
 			)
 		)
+		.subcommand(Command::new("check")
+			.about("Validate theme files, all themes by default")
+			.arg(Arg::new("theme")
+				.required(false)
+				.takes_value(true)
+				.multiple_values(true)
+				.possible_values(themes)
+				.help("Themes to check")
+			)
+		)
+		.subcommand(Command::new("toggle-mode")
+			.about("Apply the light or dark counterpart of the current theme")
+			.args([
+				Arg::new("mode")
+					.short('m')
+					.long("mode")
+					.takes_value(true)
+					.possible_values(["light", "dark"])
+					.help("Switch to this mode only if the current theme is not already in it, i.e from a scheduled extra"),
+				Arg::new("dry-run")
+					.long("dry-run")
+					.takes_value(false)
+					.help("See possible errors when applying the counterpart without changing disk files"),
+				Arg::new("strict")
+					.long("strict")
+					.takes_value(false)
+					.help("Refuse to apply the counterpart if its theme file has errors")
+			])
+		)
 		.subcommand(Command::new("apply")
 			.alias("a")
 			.about("Apply specified theme")
@@ -88,7 +203,18 @@ pub fn init<'a>(app: Command<'a>, themes: &'a [&'a str], patterns: &'a [&'a str]
 				Arg::new("dry-run")
 					.long("dry-run")
 					.takes_value(false)
-					.help("See possible errors when applying the theme without changing disk files. It does not effectively change theme.")
+					.help("See possible errors when applying the theme without changing disk files. It does not effectively change theme."),
+
+				Arg::new("strict")
+					.long("strict")
+					.takes_value(false)
+					.help("Refuse to apply the theme if it does not pass `gtheme theme check`"),
+
+				Arg::new("force")
+					.short('f')
+					.long("force")
+					.takes_value(false)
+					.help("Rewrite every output and execute every post-script, even if outputs did not change")
 			])
 		)
 	);
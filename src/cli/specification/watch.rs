@@ -0,0 +1,18 @@
+use clap::{Arg, Command};
+
+pub fn init(app: Command) -> Command {
+    app.subcommand(
+        Command::new("watch")
+            .alias("w")
+            .about("Re-render patterns of the current desktop whenever they, the current theme or user settings change")
+            .arg(
+                Arg::new("debounce")
+                    .short('d')
+                    .long("debounce")
+                    .takes_value(true)
+                    .value_name("ms")
+                    .default_value("300")
+                    .help("Time without changes to wait for before re-rendering"),
+            ),
+    )
+}
@@ -0,0 +1,252 @@
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+use crate::core;
+
+// User files a desktop install was about to overwrite. Each backup is a directory under
+// ~/.gtheme/backups with a `backup.json` index and a `files` directory mirroring the
+// absolute path of every saved file (i.e, files/home/user/.config/kitty)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    id: String,
+    date: String,
+    // Desktop whose install triggered the backup
+    desktop: String,
+    paths: Vec<String>,
+}
+
+impl Backup {
+    fn get_backups_dir() -> PathBuf {
+        Path::new(&core::expand_path(core::GTHEME_MISC)).join("backups")
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+    pub fn get_date(&self) -> &String {
+        &self.date
+    }
+    pub fn get_desktop(&self) -> &String {
+        &self.desktop
+    }
+    pub fn get_paths(&self) -> &Vec<String> {
+        &self.paths
+    }
+
+    fn get_path(&self) -> PathBuf {
+        Self::get_backups_dir().join(&self.id)
+    }
+
+    // Where a saved path is kept inside the backup
+    fn get_copy_path(&self, path: &str) -> PathBuf {
+        self.get_path()
+            .join("files")
+            .join(path.trim_start_matches('/'))
+    }
+
+    // Copies the given paths into a new backup. Returns None if any of them could not be
+    // saved, in which case nothing is kept
+    pub fn create(desktop: &str, paths: &[PathBuf]) -> Option<Backup> {
+        let now = Local::now();
+        let mut id = now.format("%Y-%m-%d_%H-%M-%S").to_string();
+        // Backups made within the same second
+        let mut suffix = 1;
+        while Self::get_backups_dir().join(&id).exists() {
+            id = format!("{}_{}", now.format("%Y-%m-%d_%H-%M-%S"), suffix);
+            suffix += 1;
+        }
+        let backup = Backup {
+            id,
+            date: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            desktop: desktop.to_string(),
+            paths: paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+        };
+
+        for path in &backup.paths {
+            info!("Backing up |{}|...", path);
+            if !copy(Path::new(path), &backup.get_copy_path(path)) {
+                backup.remove();
+                return None;
+            }
+        }
+        let content = serde_json::to_string_pretty(&backup).unwrap();
+        let index = backup.get_path().join("backup.json");
+        if let Err(e) = fs::write(&index, content) {
+            error!("Could not write backup |{}|: |{}|", index.display(), e);
+            backup.remove();
+            return None;
+        }
+        info!("Saved backup |{}|", backup.get_id());
+        Some(backup)
+    }
+
+    fn remove(&self) {
+        if let Err(e) = fs::remove_dir_all(self.get_path()) {
+            error!("Could not remove backup |{}|: |{}|", self.get_id(), e);
+        }
+    }
+
+    // Backups are returned from oldest to newest
+    pub fn get_backups() -> Vec<Backup> {
+        let mut backups = Vec::new();
+        for entry in core::get_files(&Self::get_backups_dir()) {
+            let path = entry.path().join("backup.json");
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Could not read backup |{}|: |{}|", path.display(), e);
+                    continue;
+                }
+            };
+            match serde_json::from_str::<Backup>(&content) {
+                Ok(backup) => backups.push(backup),
+                Err(e) => error!("Could not parse backup |{}|: |{}|", path.display(), e),
+            }
+        }
+        backups.sort_by(|a, b| a.get_id().cmp(b.get_id()));
+        backups
+    }
+
+    // Puts back every saved path as it was before gtheme first overwrote it, which is
+    // its oldest backup. Returns the restored paths, or None if any of them failed
+    pub fn restore_all(dry_run: bool) -> Option<Vec<String>> {
+        let mut oldest: BTreeMap<String, Backup> = BTreeMap::new();
+        for backup in Self::get_backups() {
+            for path in backup.get_paths() {
+                oldest.entry(path.clone()).or_insert_with(|| backup.clone());
+            }
+        }
+
+        let mut success = true;
+        for (path, backup) in &oldest {
+            info!("Restoring |{}| from backup |{}|...", path, backup.get_id());
+            if dry_run {
+                continue;
+            }
+            let target = Path::new(path);
+            let removed = if target.is_symlink() {
+                fs::remove_file(target).is_ok()
+            } else {
+                !target.exists() || fs_extra::remove_items(&[target]).is_ok()
+            };
+            if !removed {
+                error!("Could not remove |{}| to restore it", path);
+                success = false;
+                continue;
+            }
+            success &= copy(&backup.get_copy_path(path), target);
+        }
+        success.then(|| oldest.into_keys().collect())
+    }
+}
+
+// Copies `from` as it is, links are copied as links instead of what they point to, so
+// broken links can be saved and links to dotfiles are restored as links
+fn copy(from: &Path, to: &Path) -> bool {
+    if let Err(e) = copy_entry(from, to) {
+        error!(
+            "Could not copy |{}| into |{}|: |{}|",
+            from.display(),
+            to.display(),
+            e
+        );
+        return false;
+    }
+    true
+}
+
+fn copy_entry(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_type = fs::symlink_metadata(from)?.file_type();
+    if file_type.is_symlink() {
+        symlink(fs::read_link(from)?, to)
+    } else if file_type.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing;
+
+    #[test]
+    fn test_get_copy_path() {
+        let backup = Backup {
+            id: "2026-10-18_11-02-03".to_string(),
+            date: String::new(),
+            desktop: "test".to_string(),
+            paths: vec![],
+        };
+        let copy = backup.get_copy_path("/home/user/.config/kitty");
+        assert!(copy.ends_with("backups/2026-10-18_11-02-03/files/home/user/.config/kitty"));
+    }
+
+    #[test]
+    fn test_create_restore() {
+        testing::with_home("backup", |home| {
+            let config = home.join(".config");
+            fs::create_dir_all(config.join("kitty/themes")).unwrap();
+            fs::write(config.join("kitty/kitty.conf"), "font_size 12").unwrap();
+            fs::write(config.join("kitty/themes/nord.conf"), "").unwrap();
+            fs::create_dir_all(home.join("dotfiles/nvim")).unwrap();
+            symlink(home.join("dotfiles/nvim"), config.join("nvim")).unwrap();
+            symlink(home.join("missing"), config.join("broken")).unwrap();
+            let paths = vec![
+                config.join("kitty"),
+                config.join("nvim"),
+                config.join("broken"),
+            ];
+
+            let backup = Backup::create("test", &paths).unwrap();
+            assert!(backup
+                .get_copy_path(&paths[1].display().to_string())
+                .is_symlink());
+            assert_eq!(Backup::get_backups().len(), 1);
+
+            // What a desktop install would leave in their place
+            fs_extra::remove_items(&[config.join("kitty")]).unwrap();
+            fs::write(config.join("kitty"), "installed").unwrap();
+            fs::remove_file(config.join("nvim")).unwrap();
+            fs::create_dir_all(config.join("nvim")).unwrap();
+            fs::remove_file(config.join("broken")).unwrap();
+
+            assert_eq!(Backup::restore_all(true).unwrap().len(), 3);
+            assert!(config.join("kitty").is_file());
+            let restored = Backup::restore_all(false).unwrap();
+            assert_eq!(restored.len(), 3);
+            assert_eq!(
+                fs::read_to_string(config.join("kitty/kitty.conf")).unwrap(),
+                "font_size 12"
+            );
+            assert!(config.join("kitty/themes/nord.conf").exists());
+            assert_eq!(
+                fs::read_link(config.join("nvim")).unwrap(),
+                home.join("dotfiles/nvim")
+            );
+            assert!(home.join("dotfiles/nvim").is_dir());
+            assert_eq!(
+                fs::read_link(config.join("broken")).unwrap(),
+                home.join("missing")
+            );
+        });
+    }
+}
@@ -1,12 +1,15 @@
-use crate::core::desktop::DesktopFile;
+use crate::core::desktop::{DesktopFile, InstallMode, Manifest, ReadManifest};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions};
+use std::path::Path;
 use toml;
 
 use std::io::prelude::*;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DesktopInfo {
     author: String,
     description: String,
@@ -14,10 +17,27 @@ pub struct DesktopInfo {
     #[serde(default)]
     optional_dependencies: Vec<String>,
     credits: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install_mode: Option<InstallMode>,
+    // Package of a dependency for each package manager, overriding the known ones:
+    //  [packages.pacman]
+    //  "JetBrains Mono" = "ttf-jetbrains-mono"
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    packages: BTreeMap<String, BTreeMap<String, String>>,
 }
 
 impl DesktopInfo {
     pub fn new(desktop: &DesktopFile) -> Self {
+        Self::from_manifest(desktop, &Manifest::read(desktop.get_path()))
+    }
+
+    // Info of a desktop whose manifest was already read
+    pub fn from_manifest(desktop: &DesktopFile, manifest: &ReadManifest) -> Self {
+        match manifest {
+            Ok(Some(manifest)) => return manifest.get_info().clone(),
+            Err(_) => return Self::default(),
+            Ok(None) => (),
+        }
         let path = format!("{}/desktop_info.toml", desktop.get_path());
 
         let mut file = match File::open(&path) {
@@ -88,6 +108,18 @@ impl DesktopInfo {
     pub fn get_credits(&self) -> &String {
         &self.credits
     }
+    pub fn get_install_mode(&self) -> &Option<InstallMode> {
+        &self.install_mode
+    }
+    pub fn get_packages(&self) -> &BTreeMap<String, BTreeMap<String, String>> {
+        &self.packages
+    }
+
+    // Whether the desktop declares its info, either in its manifest or desktop_info.toml
+    pub fn exists(desktop: &DesktopFile) -> bool {
+        Manifest::exists(desktop)
+            || Path::new(&format!("{}/desktop_info.toml", desktop.get_path())).exists()
+    }
 }
 
 impl Default for DesktopInfo {
@@ -98,6 +130,8 @@ impl Default for DesktopInfo {
             dependencies: vec![],
             optional_dependencies: vec![],
             credits: "".to_string(),
+            install_mode: None,
+            packages: BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,287 @@
+use log::warn;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::core;
+use crate::core::config::DesktopInfo;
+use crate::core::desktop::{Desktop, DesktopFile};
+
+mod package;
+
+pub use package::PackageManager;
+
+// Fontconfig configuration, where the font directories are declared
+const FONTCONFIG_FILES: [&str; 3] = [
+    "/etc/fonts/fonts.conf",
+    "/etc/fonts/local.conf",
+    "~/.config/fontconfig/fonts.conf",
+];
+const FONTCONFIG_DIRS: [&str; 2] = ["/etc/fonts/conf.d", "~/.config/fontconfig/conf.d"];
+// Searched even if fontconfig does not declare them
+const DEFAULT_FONT_DIRS: [&str; 4] = [
+    "/usr/share/fonts",
+    "/usr/local/share/fonts",
+    "~/.local/share/fonts",
+    "~/.fonts",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    // Executable in $PATH
+    Bin,
+    // Font family in any fontconfig directory
+    Font,
+    // Any file or directory
+    File,
+}
+
+impl std::fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kind = match self {
+            DependencyKind::Bin => "bin",
+            DependencyKind::Font => "font",
+            DependencyKind::File => "file",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+// Entry of the dependencies of a desktop info. Entries may be typed with a prefix, as in
+// `bin:polybar`, `font:JetBrains Mono` or `file:~/.xinitrc`, untyped ones are binaries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    kind: DependencyKind,
+    name: String,
+    optional: bool,
+}
+
+impl Dependency {
+    // None if the entry has no name. Font names need a letter or digit, as they are
+    // compared without the rest
+    pub fn from(entry: &str, optional: bool) -> Option<Self> {
+        let (kind, name) = match entry.split_once(':') {
+            Some(("bin", name)) => (DependencyKind::Bin, name),
+            Some(("font", name)) => (DependencyKind::Font, name),
+            Some(("file", name)) => (DependencyKind::File, name),
+            _ => (DependencyKind::Bin, entry),
+        };
+        let name = name.trim();
+        if name.is_empty() || (kind == DependencyKind::Font && normalize(name).is_empty()) {
+            warn!("Ignoring dependency |{}|, it has no name", entry);
+            return None;
+        }
+        Some(Dependency {
+            kind,
+            name: name.to_string(),
+            optional,
+        })
+    }
+
+    // Required dependencies of a desktop, followed by the optional ones if asked for
+    pub fn get_dependencies(info: &DesktopInfo, optional: bool) -> Vec<Dependency> {
+        let mut dependencies: Vec<Dependency> = info
+            .get_dependencies()
+            .iter()
+            .filter_map(|entry| Dependency::from(entry, false))
+            .collect();
+        if optional {
+            dependencies.extend(
+                info.get_optional_dependencies()
+                    .iter()
+                    .filter_map(|entry| Dependency::from(entry, true)),
+            );
+        }
+        dependencies
+    }
+
+    // Required dependencies of a desktop that are not present. Desktops are not installed
+    // while any of them is missing
+    pub fn get_missing(desktop: &DesktopFile) -> Vec<Dependency> {
+        let dependencies = Self::get_dependencies(&DesktopInfo::new(desktop), false);
+        Resolver::new(&desktop.to_desktop())
+            .resolve(dependencies)
+            .into_iter()
+            .filter(|(_, present)| !present)
+            .map(|(dependency, _)| dependency)
+            .collect()
+    }
+
+    pub fn get_kind(&self) -> DependencyKind {
+        self.kind
+    }
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+}
+
+// Checks whether dependencies are present in the system. Fonts shipped with the desktop
+// count as present, as they are installed along with it
+pub struct Resolver {
+    fonts: BTreeSet<String>,
+}
+
+impl Resolver {
+    pub fn new(desktop: &Desktop) -> Self {
+        let font_dirs = get_font_dirs();
+        let mut fonts = BTreeSet::new();
+        for dir in &font_dirs {
+            add_fonts(dir, &mut fonts);
+        }
+        for (source, target) in desktop.get_config_files() {
+            if font_dirs.iter().any(|dir| target.starts_with(dir)) {
+                add_fonts(&source, &mut fonts);
+            }
+        }
+        Resolver { fonts }
+    }
+
+    pub fn is_present(&self, dependency: &Dependency) -> bool {
+        let name = dependency.get_name();
+        match dependency.get_kind() {
+            DependencyKind::Bin => {
+                if name.contains('/') {
+                    return is_executable(Path::new(&core::expand_path(name)));
+                }
+                env::var_os("PATH").is_some_and(|path| {
+                    env::split_paths(&path).any(|dir| is_executable(&dir.join(name)))
+                })
+            }
+            DependencyKind::Font => {
+                let family = normalize(name);
+                self.fonts.iter().any(|font| font.contains(&family))
+            }
+            DependencyKind::File => Path::new(&core::expand_path(name)).exists(),
+        }
+    }
+
+    // Every dependency along with whether it is present
+    pub fn resolve(&self, dependencies: Vec<Dependency>) -> Vec<(Dependency, bool)> {
+        dependencies
+            .into_iter()
+            .map(|dependency| {
+                let present = self.is_present(&dependency);
+                (dependency, present)
+            })
+            .collect()
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|md| md.is_file() && md.permissions().mode() & 0o111 != 0)
+}
+
+// Font names are compared without case, spaces or dashes, so `font:JetBrains Mono`
+// matches `JetBrainsMono-Regular.ttf`
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn add_fonts(path: &Path, fonts: &mut BTreeSet<String>) {
+    if path.is_dir() {
+        for entry in core::get_files(path) {
+            add_fonts(&entry.path(), fonts);
+        }
+    } else if let Some(stem) = path.file_stem() {
+        fonts.insert(normalize(&stem.to_string_lossy()));
+    }
+}
+
+// `<dir>` entries of the fontconfig configuration, plus the usual font directories
+fn get_font_dirs() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = FONTCONFIG_FILES
+        .iter()
+        .map(|file| PathBuf::from(core::expand_path(file)))
+        .collect();
+    for dir in FONTCONFIG_DIRS {
+        files.extend(
+            core::get_files(Path::new(&core::expand_path(dir)))
+                .into_iter()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "conf")),
+        );
+    }
+
+    let mut dirs: Vec<PathBuf> = DEFAULT_FONT_DIRS
+        .iter()
+        .map(|dir| PathBuf::from(core::expand_path(dir)))
+        .collect();
+    for file in files {
+        if let Ok(content) = fs::read_to_string(&file) {
+            dirs.extend(parse_font_dirs(&content));
+        }
+    }
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn parse_font_dirs(content: &str) -> Vec<PathBuf> {
+    let re = Regex::new(r"<dir([^>]*)>\s*([^<]+?)\s*</dir>").unwrap();
+    let data_home =
+        env::var("XDG_DATA_HOME").unwrap_or_else(|_| core::expand_path("~/.local/share"));
+    re.captures_iter(content)
+        .map(|captures| {
+            if captures[1].contains("prefix=\"xdg\"") {
+                Path::new(&data_home).join(&captures[2])
+            } else {
+                PathBuf::from(core::expand_path(&captures[2]))
+            }
+        })
+        .filter(|dir| dir.is_absolute())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency() {
+        let font = Dependency::from("font:JetBrains Mono", false).unwrap();
+        assert_eq!(font.get_kind(), DependencyKind::Font);
+        assert_eq!(font.get_name(), "JetBrains Mono");
+        assert_eq!(
+            Dependency::from("polybar", true).unwrap().get_kind(),
+            DependencyKind::Bin
+        );
+        assert_eq!(
+            Dependency::from("file:~/.xinitrc", false)
+                .unwrap()
+                .get_kind(),
+            DependencyKind::File
+        );
+
+        let resolver = Resolver {
+            fonts: BTreeSet::from([normalize("JetBrainsMono-Regular")]),
+        };
+        assert!(resolver.is_present(&font));
+        assert!(!resolver.is_present(&Dependency::from("font:Fira Code", false).unwrap()));
+        assert!(resolver.is_present(&Dependency::from("bin:sh", false).unwrap()));
+        assert!(resolver.is_present(&Dependency::from("file:/", false).unwrap()));
+
+        assert!(Dependency::from("font:", false).is_none());
+        assert!(Dependency::from("font: -_ ", false).is_none());
+        assert!(Dependency::from("bin: ", false).is_none());
+    }
+
+    #[test]
+    fn test_parse_font_dirs() {
+        let content = r#"<dir>/usr/share/fonts</dir>
+            <dir prefix="xdg">fonts</dir>
+            <dir>fonts</dir>"#;
+        let dirs = parse_font_dirs(content);
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(dirs[0], PathBuf::from("/usr/share/fonts"));
+        assert!(dirs[1].ends_with("fonts"));
+    }
+}
@@ -0,0 +1,266 @@
+use log::{error, warn};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::core::dependency::{Dependency, DependencyKind};
+
+const OS_RELEASE: &str = "/etc/os-release";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Pacman,
+    Apt,
+    Dnf,
+    Xbps,
+    Nix,
+}
+
+// Packages known to be named differently from the dependency, for each manager in the
+// same order as `PackageManager::index`. An empty name means no package is known
+const PACKAGES: [(&str, [&str; 5]); 6] = [
+    (
+        "fc-cache",
+        [
+            "fontconfig",
+            "fontconfig",
+            "fontconfig",
+            "fontconfig",
+            "fontconfig",
+        ],
+    ),
+    (
+        "notify-send",
+        [
+            "libnotify",
+            "libnotify-bin",
+            "libnotify",
+            "libnotify",
+            "libnotify",
+        ],
+    ),
+    (
+        "xrdb",
+        [
+            "xorg-xrdb",
+            "x11-xserver-utils",
+            "xrdb",
+            "xrdb",
+            "xorg.xrdb",
+        ],
+    ),
+    ("nvim", ["neovim", "neovim", "neovim", "neovim", "neovim"]),
+    (
+        "JetBrains Mono",
+        [
+            "ttf-jetbrains-mono",
+            "fonts-jetbrains-mono",
+            "jetbrains-mono-fonts",
+            "",
+            "jetbrains-mono",
+        ],
+    ),
+    (
+        "Noto Sans",
+        [
+            "noto-fonts",
+            "fonts-noto",
+            "google-noto-sans-fonts",
+            "noto-fonts-ttf",
+            "noto-fonts",
+        ],
+    ),
+];
+
+impl PackageManager {
+    pub fn from(name: &str) -> Option<Self> {
+        match name {
+            "pacman" => Some(PackageManager::Pacman),
+            "apt" => Some(PackageManager::Apt),
+            "dnf" => Some(PackageManager::Dnf),
+            "xbps" => Some(PackageManager::Xbps),
+            "nix" => Some(PackageManager::Nix),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PackageManager::Pacman => "pacman",
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Xbps => "xbps",
+            PackageManager::Nix => "nix",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            PackageManager::Pacman => 0,
+            PackageManager::Apt => 1,
+            PackageManager::Dnf => 2,
+            PackageManager::Xbps => 3,
+            PackageManager::Nix => 4,
+        }
+    }
+
+    // Package manager of the running distro, from the ID and ID_LIKE of /etc/os-release
+    pub fn detect() -> Option<Self> {
+        let content = match fs::read_to_string(OS_RELEASE) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Could not read |{}|: |{}|", OS_RELEASE, e);
+                return None;
+            }
+        };
+        let manager = Self::from_os_release(&content);
+        if manager.is_none() {
+            error!("Could not detect the package manager of this distro");
+        }
+        manager
+    }
+
+    fn from_os_release(content: &str) -> Option<Self> {
+        let mut ids = Vec::new();
+        for line in content.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                if key == "ID" || key == "ID_LIKE" {
+                    ids.extend(value.trim_matches('"').split_whitespace().map(String::from));
+                }
+            }
+        }
+        ids.iter().find_map(|id| match id.as_str() {
+            "arch" | "manjaro" | "endeavouros" | "artix" => Some(PackageManager::Pacman),
+            "debian" | "ubuntu" | "linuxmint" | "pop" => Some(PackageManager::Apt),
+            "fedora" | "rhel" | "centos" | "rocky" | "almalinux" => Some(PackageManager::Dnf),
+            "void" => Some(PackageManager::Xbps),
+            "nixos" => Some(PackageManager::Nix),
+            _ => None,
+        })
+    }
+
+    // Package providing a dependency. The desktop mapping wins over the known packages, and
+    // binaries default to a package with their name. None if no package is known
+    pub fn get_package(
+        &self,
+        dependency: &Dependency,
+        overrides: &BTreeMap<String, BTreeMap<String, String>>,
+    ) -> Option<String> {
+        let name = dependency.get_name();
+        let package = overrides
+            .get(self.get_name())
+            .and_then(|packages| packages.get(name))
+            .cloned()
+            .or_else(|| {
+                PACKAGES
+                    .iter()
+                    .find(|(known, _)| known == name)
+                    .map(|(_, packages)| packages[self.index()].to_string())
+            });
+        match package {
+            Some(package) if package.is_empty() => None,
+            Some(package) => Some(package),
+            None if dependency.get_kind() == DependencyKind::Bin => Some(name.clone()),
+            None => None,
+        }
+    }
+
+    // Command installing the packages of the given dependencies. Dependencies without a
+    // known package are left out with a warning
+    pub fn get_install_command(
+        &self,
+        dependencies: &[Dependency],
+        overrides: &BTreeMap<String, BTreeMap<String, String>>,
+    ) -> Option<String> {
+        let mut packages: Vec<String> = Vec::new();
+        for dependency in dependencies {
+            match self.get_package(dependency, overrides) {
+                Some(package) => {
+                    for package in package.split_whitespace() {
+                        if !packages.iter().any(|added| added == package) {
+                            packages.push(package.to_string());
+                        }
+                    }
+                }
+                None => warn!(
+                    "No |{}| package known for |{}:{}|, map it in |[packages.{}]| of the desktop info",
+                    self.get_name(),
+                    dependency.get_kind(),
+                    dependency.get_name(),
+                    self.get_name()
+                ),
+            }
+        }
+        if packages.is_empty() {
+            return None;
+        }
+
+        let command = match self {
+            PackageManager::Pacman => "sudo pacman -S --needed",
+            PackageManager::Apt => "sudo apt install",
+            PackageManager::Dnf => "sudo dnf install",
+            PackageManager::Xbps => "sudo xbps-install -S",
+            // Only detected on NixOS, whose default channel is named `nixos`
+            PackageManager::Nix => {
+                let attributes: Vec<String> = packages
+                    .iter()
+                    .map(|package| format!("nixos.{}", package))
+                    .collect();
+                return Some(format!("nix-env -iA {}", attributes.join(" ")));
+            }
+        };
+        Some(format!("{} {}", command, packages.join(" ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_os_release() {
+        let content = "NAME=\"Linux Mint\"\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\n";
+        assert_eq!(
+            PackageManager::from_os_release(content),
+            Some(PackageManager::Apt)
+        );
+        assert_eq!(
+            PackageManager::from_os_release("ID=\"endeavouros\"\nID_LIKE=arch\n"),
+            Some(PackageManager::Pacman)
+        );
+        assert_eq!(
+            PackageManager::from_os_release("NAME=NixOS\nID=nixos\n"),
+            Some(PackageManager::Nix)
+        );
+        assert_eq!(PackageManager::from_os_release("ID=gentoo\n"), None);
+    }
+
+    #[test]
+    fn test_get_install_command() {
+        let dependencies = vec![
+            Dependency::from("kitty", false).unwrap(),
+            Dependency::from("notify-send", false).unwrap(),
+            Dependency::from("font:JetBrains Mono", false).unwrap(),
+            Dependency::from("font:Unknown Font", false).unwrap(),
+            Dependency::from("file:~/.xinitrc", false).unwrap(),
+        ];
+        let overrides = BTreeMap::from([(
+            "xbps".to_string(),
+            BTreeMap::from([(
+                "JetBrains Mono".to_string(),
+                "font-jetbrains-mono".to_string(),
+            )]),
+        )]);
+        assert_eq!(
+            PackageManager::Apt.get_install_command(&dependencies, &overrides),
+            Some("sudo apt install kitty libnotify-bin fonts-jetbrains-mono".to_string())
+        );
+        assert_eq!(
+            PackageManager::Xbps.get_install_command(&dependencies, &overrides),
+            Some("sudo xbps-install -S kitty libnotify font-jetbrains-mono".to_string())
+        );
+        assert_eq!(
+            PackageManager::Nix.get_install_command(&dependencies[..1], &overrides),
+            Some("nix-env -iA nixos.kitty".to_string())
+        );
+    }
+}
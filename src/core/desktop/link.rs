@@ -0,0 +1,110 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+// How the files of a desktop are installed. Set with `install_mode` in the desktop info,
+// or for every desktop with the `install_mode` user setting, which takes precedence
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallMode {
+    #[default]
+    Copy,
+    // Every mapped entry is a symlink into the desktop, like GNU stow does, so edits
+    // made in place end up in the desktop directory
+    Symlink,
+}
+
+impl InstallMode {
+    pub fn from(value: &str) -> Option<Self> {
+        match value {
+            "copy" => Some(InstallMode::Copy),
+            "symlink" => Some(InstallMode::Symlink),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    Linked,
+    Missing,
+    // The link exists but what it points to does not
+    Broken,
+    // Something else took the place of the link
+    Hijacked,
+}
+
+impl std::fmt::Display for LinkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let state = match self {
+            LinkState::Linked => "linked",
+            LinkState::Missing => "missing",
+            LinkState::Broken => "broken",
+            LinkState::Hijacked => "hijacked",
+        };
+        write!(f, "{}", state)
+    }
+}
+
+// Links `target` to `source`. Whatever was at `target` is replaced, it was backed up
+// before if it did not belong to gtheme
+pub fn create(source: &Path, target: &Path) -> bool {
+    if (target.exists() || target.is_symlink()) && fs_extra::remove_items(&[target]).is_err() {
+        error!("Could not replace |{}| with a link", target.display());
+        return false;
+    }
+    let parent = target.parent().unwrap_or(Path::new("/"));
+    if let Err(e) = fs::create_dir_all(parent).and_then(|_| symlink(source, target)) {
+        error!(
+            "Could not link |{}| to |{}|: |{}|",
+            target.display(),
+            source.display(),
+            e
+        );
+        return false;
+    }
+    true
+}
+
+pub fn points_into(target: &Path, dir: &Path) -> bool {
+    fs::read_link(target).is_ok_and(|destination| destination.starts_with(dir))
+}
+
+pub fn get_state(source: &Path, target: &Path) -> LinkState {
+    match fs::read_link(target) {
+        Ok(_) if !target.exists() => LinkState::Broken,
+        Ok(destination) if destination == source => LinkState::Linked,
+        Ok(_) => LinkState::Hijacked,
+        Err(_) if target.exists() => LinkState::Hijacked,
+        Err(_) => LinkState::Missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_state() {
+        let dir = std::env::temp_dir().join(format!("gtheme-link-{}", std::process::id()));
+        let source = dir.join("desktop/kitty");
+        let target = dir.join("config/kitty");
+        fs::create_dir_all(&source).unwrap();
+
+        assert_eq!(get_state(&source, &target), LinkState::Missing);
+        assert!(create(&source, &target));
+        assert_eq!(get_state(&source, &target), LinkState::Linked);
+        assert!(points_into(&target, &dir.join("desktop")));
+
+        fs::remove_dir(&source).unwrap();
+        assert_eq!(get_state(&source, &target), LinkState::Broken);
+
+        fs::remove_file(&target).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        assert_eq!(get_state(&source, &target), LinkState::Hijacked);
+        assert!(!points_into(&target, &dir.join("desktop")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core;
+use crate::core::desktop::link::{self, InstallMode};
+use crate::core::desktop::Desktop;
+
+const LOCKFILE: &str = "installed.json";
+
+// Files installed with the current desktop, kept in ~/.gtheme/installed.json. Uninstalling
+// removes exactly these files, so anything the user added next to them is left alone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    desktop: String,
+    date: String,
+    // Targets of the desktop mapping, emptied directories are removed up to them
+    targets: Vec<String>,
+    // Content hash of every installed file. Pattern outputs are rewritten on every theme
+    // change, so they have no hash and are never reported as modified
+    files: BTreeMap<String, Option<String>>,
+    // Links of desktops installed in symlink mode, from the link to the desktop entry
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    links: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    fn get_path() -> PathBuf {
+        Path::new(&core::expand_path(core::GTHEME_MISC)).join(LOCKFILE)
+    }
+
+    // Records the files of a desktop that was just installed, but those `kept` by the user
+    // instead of the ones of the desktop
+    pub fn new(desktop: &Desktop, install_mode: InstallMode, kept: &BTreeSet<String>) -> Self {
+        let targets = desktop
+            .get_config_files()
+            .into_iter()
+            .map(|(source, target)| (target.display().to_string(), source.display().to_string()))
+            .collect::<BTreeMap<_, _>>();
+        if install_mode == InstallMode::Symlink {
+            return Lockfile {
+                desktop: desktop.get_name().clone(),
+                date: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                targets: targets.keys().cloned().collect(),
+                files: BTreeMap::new(),
+                links: targets,
+            };
+        }
+
+        let outputs: BTreeSet<String> = desktop
+            .get_patterns()
+            .iter()
+            .filter_map(|pattern| pattern.to_pattern().get_output().clone())
+            .collect();
+        let files = desktop
+            .get_installed_files()
+            .into_iter()
+            .map(|(_, target)| (target.display().to_string(), target))
+            .filter(|(path, _)| !kept.contains(path))
+            .map(|(path, target)| {
+                let hash = if outputs.contains(&path) {
+                    None
+                } else {
+                    get_hash(&target)
+                };
+                (path, hash)
+            })
+            .collect();
+
+        Lockfile {
+            desktop: desktop.get_name().clone(),
+            date: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            targets: targets.into_keys().collect(),
+            files,
+            links: BTreeMap::new(),
+        }
+    }
+
+    // None if no desktop was installed since gtheme started keeping track of files
+    pub fn read() -> Option<Self> {
+        let path = Self::get_path();
+        if !path.exists() {
+            return None;
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Could not read |{}|: |{}|", path.display(), e);
+                return None;
+            }
+        };
+        match serde_json::from_str(&content) {
+            Ok(lockfile) => Some(lockfile),
+            Err(e) => {
+                error!("Could not parse |{}|: |{}|", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) -> bool {
+        let path = Self::get_path();
+        let content = serde_json::to_string_pretty(self).unwrap();
+        let result =
+            fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, content));
+        if let Err(e) = result {
+            error!("Could not save |{}|: |{}|", path.display(), e);
+            return false;
+        }
+        true
+    }
+
+    pub fn get_desktop(&self) -> &String {
+        &self.desktop
+    }
+    pub fn get_date(&self) -> &String {
+        &self.date
+    }
+    pub fn get_files(&self) -> &BTreeMap<String, Option<String>> {
+        &self.files
+    }
+    pub fn get_links(&self) -> &BTreeMap<String, String> {
+        &self.links
+    }
+
+    // An installed file is modified if its content changed since it was installed
+    pub fn is_modified(&self, path: &str) -> bool {
+        match self.files.get(path) {
+            Some(Some(hash)) => get_hash(Path::new(path)).as_ref() != Some(hash),
+            _ => false,
+        }
+    }
+
+    pub fn get_modified(&self) -> Vec<&String> {
+        self.files
+            .keys()
+            .filter(|path| Path::new(path).exists() && self.is_modified(path))
+            .collect()
+    }
+
+    // Installed files and links that the desktop no longer provides, left by an older
+    // version of it
+    pub fn get_orphans(&self, desktop: &Desktop) -> Vec<&String> {
+        let provided: BTreeSet<String> = desktop
+            .get_installed_files()
+            .into_iter()
+            .chain(desktop.get_config_files())
+            .map(|(_, target)| target.display().to_string())
+            .collect();
+        self.files
+            .keys()
+            .chain(self.links.keys())
+            .filter(|path| !provided.contains(*path))
+            .filter(|path| Path::new(path).exists() || Path::new(path).is_symlink())
+            .collect()
+    }
+
+    // Whether everything at `path` was installed by gtheme and is unchanged since then
+    pub fn owns(&self, path: &Path) -> bool {
+        if let Some(source) = self.links.get(&path.display().to_string()) {
+            return link::get_state(Path::new(source), path) == link::LinkState::Linked;
+        }
+        get_tree(path).iter().all(|file| {
+            let file = file.display().to_string();
+            self.files.contains_key(&file) && !self.is_modified(&file)
+        })
+    }
+
+    // Removes the installed files, keeping the ones the user modified, and the directories
+    // left empty. Links are only removed while they still point into the desktop. The
+    // lockfile is removed afterwards
+    pub fn uninstall(&self) {
+        for (path, source) in &self.links {
+            let target = Path::new(path);
+            if !link::points_into(target, Path::new(source)) {
+                if target.exists() || target.is_symlink() {
+                    warn!(
+                        "|{}| no longer links to the desktop, leaving it in place",
+                        path
+                    );
+                }
+                continue;
+            }
+            info!("Removing link |{}|...", path);
+            if let Err(e) = fs::remove_file(target) {
+                error!("Could not remove |{}|: |{}|", path, e);
+            }
+        }
+
+        let mut parents = BTreeSet::new();
+        for path in self.files.keys() {
+            let file = Path::new(path);
+            if !file.exists() && !file.is_symlink() {
+                continue;
+            }
+            if self.is_modified(path) {
+                warn!(
+                    "|{}| was modified after being installed, leaving it in place",
+                    path
+                );
+                continue;
+            }
+            info!("Removing |{}|...", path);
+            if let Err(e) = fs::remove_file(file) {
+                error!("Could not remove |{}|: |{}|", path, e);
+                continue;
+            }
+            parents.extend(file.ancestors().skip(1).map(Path::to_path_buf));
+        }
+
+        // Deepest directories first, so parents are empty by the time they are reached
+        for dir in parents.iter().rev() {
+            let inside_target = self.targets.iter().any(|target| dir.starts_with(target));
+            if inside_target && fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none())
+            {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+
+        if let Err(e) = fs::remove_file(Self::get_path()) {
+            error!("Could not remove |{}|: |{}|", Self::get_path().display(), e);
+        }
+    }
+}
+
+// Every file below `path`, or `path` itself if it is not a directory
+pub fn get_tree(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() || path.is_symlink() {
+        return vec![path.to_path_buf()];
+    }
+    core::get_files(path)
+        .into_iter()
+        .flat_map(|entry| get_tree(&entry.path()))
+        .collect()
+}
+
+fn get_hash(path: &Path) -> Option<String> {
+    match fs::read(path) {
+        Ok(content) => Some(hex::encode(Sha256::digest(&content))),
+        Err(e) => {
+            warn!("Could not hash |{}|: |{}|", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockfile() {
+        let dir = std::env::temp_dir().join(format!("gtheme-lockfile-{}", std::process::id()));
+        fs::create_dir_all(dir.join("kitty")).unwrap();
+        let installed = dir.join("kitty/kitty.conf");
+        let output = dir.join("kitty/colors.conf");
+        fs::write(&installed, "font_size 12").unwrap();
+        fs::write(&output, "background #000000").unwrap();
+
+        let lockfile = Lockfile {
+            desktop: "test".to_string(),
+            date: String::new(),
+            targets: vec![dir.join("kitty").display().to_string()],
+            files: BTreeMap::from([
+                (installed.display().to_string(), get_hash(&installed)),
+                (output.display().to_string(), None),
+            ]),
+            links: BTreeMap::new(),
+        };
+        fs::write(&output, "background #ffffff").unwrap();
+        assert!(lockfile.owns(&dir.join("kitty")));
+        assert!(lockfile.get_modified().is_empty());
+
+        fs::write(&installed, "font_size 14").unwrap();
+        assert_eq!(
+            lockfile.get_modified(),
+            vec![&installed.display().to_string()]
+        );
+        assert!(!lockfile.owns(&dir.join("kitty")));
+
+        fs::write(dir.join("kitty/user.conf"), "").unwrap();
+        assert!(!lockfile.owns(&dir.join("kitty/user.conf")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
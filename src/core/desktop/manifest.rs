@@ -0,0 +1,274 @@
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::core;
+use crate::core::config::DesktopInfo;
+use crate::core::desktop::DesktopFile;
+use crate::core::pattern::Pattern;
+use crate::core::postscript::PostScript;
+
+pub const MANIFEST_FILE: &str = "desktop.toml";
+// Outcome of reading a manifest, None for desktops without one. Errors are already logged
+pub type ReadManifest = Result<Option<Manifest>, String>;
+// Target of the fonts of legacy desktops
+pub const LEGACY_FONTS_HOME: &str = "~/.local/share/fonts/gtheme-fonts";
+
+// Everything a desktop provides, declared in `desktop.toml` at its root. Relative paths
+// are relative to the desktop directory:
+//  [info]
+//  author = "jorge"
+//  dependencies = ["kitty"]
+//  install_mode = "symlink"
+//  [patterns.kitty]
+//  path = "gtheme/patterns/kitty.pattern"
+//  output = "~/.config/kitty/colors.conf"
+//  post-script = "gtheme/post-scripts/kitty.sh"
+//  [post-scripts]
+//  desktop-exit = "gtheme/post-scripts/desktop-exit.sh"
+//  [extras]
+//  wallpaper = "gtheme/extras/wallpaper"
+//  [files]
+//  ".config" = "~/.config"
+// Desktops without a manifest keep using the directory layout (gtheme/patterns,
+// gtheme/post-scripts, gtheme/extras, .config, fonts and desktop_info.toml)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Manifest {
+    #[serde(default)]
+    info: DesktopInfo,
+    #[serde(default)]
+    patterns: BTreeMap<String, PatternEntry>,
+    // Post-scripts not bound to a pattern, like desktop-exit
+    #[serde(default)]
+    post_scripts: BTreeMap<String, String>,
+    #[serde(default)]
+    extras: BTreeMap<String, String>,
+    // Files installed with the desktop, from a path inside the desktop to its target
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PatternEntry {
+    path: String,
+    // Overrides the `<[output-file]>` header of the pattern
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    post_script: Option<String>,
+}
+
+impl PatternEntry {
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+    pub fn get_output(&self) -> &Option<String> {
+        &self.output
+    }
+    pub fn get_post_script(&self) -> &Option<String> {
+        &self.post_script
+    }
+}
+
+impl Manifest {
+    // Manifest of the desktop, None if it uses the legacy layout. An invalid manifest is
+    // an error, the legacy layout would install files somewhere else
+    pub fn read(desktop_path: &str) -> ReadManifest {
+        let path = format!("{}/{}", desktop_path, MANIFEST_FILE);
+        if !Path::new(&path).exists() {
+            return Ok(None);
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Could not read desktop manifest |{}|: |{}|", path, e);
+                return Err(e.to_string());
+            }
+        };
+        match toml::from_str(&content) {
+            Ok(manifest) => Ok(Some(manifest)),
+            Err(e) => {
+                error!("Could not parse desktop manifest |{}|: |{}|", path, e);
+                Err(e.to_string())
+            }
+        }
+    }
+
+    pub fn exists(desktop: &DesktopFile) -> bool {
+        Path::new(&format!("{}/{}", desktop.get_path(), MANIFEST_FILE)).exists()
+    }
+
+    pub fn save(&self, desktop: &DesktopFile) -> bool {
+        let path = format!("{}/{}", desktop.get_path(), MANIFEST_FILE);
+        if let Err(e) = fs::write(&path, self.to_string()) {
+            error!("Could not write desktop manifest |{}|: |{}|", path, e);
+            return false;
+        }
+        info!("Saved desktop manifest |{}|", path);
+        true
+    }
+
+    pub fn get_info(&self) -> &DesktopInfo {
+        &self.info
+    }
+    pub fn get_patterns(&self) -> &BTreeMap<String, PatternEntry> {
+        &self.patterns
+    }
+    pub fn get_post_scripts(&self) -> &BTreeMap<String, String> {
+        &self.post_scripts
+    }
+    pub fn get_extras(&self) -> &BTreeMap<String, String> {
+        &self.extras
+    }
+    pub fn get_files(&self) -> &BTreeMap<String, String> {
+        &self.files
+    }
+
+    pub fn set_extra(&mut self, name: &str, path: &str) {
+        self.extras.insert(name.to_string(), path.to_string());
+    }
+
+    // Absolute path of a path declared in the manifest
+    pub fn resolve(desktop_path: &str, path: &str) -> String {
+        let path = core::expand_path(path);
+        if Path::new(&path).is_absolute() {
+            path
+        } else {
+            format!("{}/{}", desktop_path, path)
+        }
+    }
+
+    // Manifest describing a desktop that uses the legacy layout
+    pub fn from_legacy(desktop: &DesktopFile) -> Manifest {
+        let relative = |path: &str| {
+            Path::new(path)
+                .strip_prefix(desktop.get_path())
+                .map(|relative| relative.to_string_lossy().to_string())
+                .unwrap_or_else(|_| path.to_string())
+        };
+
+        let mut post_scripts = PostScript::get_postscripts(desktop);
+        let mut patterns = BTreeMap::new();
+        for pattern_file in Pattern::get_patterns(desktop) {
+            let pattern = pattern_file.to_pattern();
+            let entry = PatternEntry {
+                path: relative(pattern_file.get_path()),
+                output: pattern
+                    .get_output()
+                    .as_ref()
+                    .map(|output| collapse_home(output)),
+                post_script: post_scripts
+                    .remove(pattern_file.get_name())
+                    .map(|postscript| relative(postscript.get_path())),
+            };
+            patterns.insert(pattern_file.get_name().clone(), entry);
+        }
+
+        let extras = PostScript::get_extras(desktop)
+            .into_iter()
+            .map(|extra| {
+                // Folder extras are declared by their folder, so the storage is found
+                let path = match extra.get_storage() {
+                    Some(_) => Path::new(extra.get_path())
+                        .parent()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                    None => extra.get_path().clone(),
+                };
+                (extra.get_name().clone(), relative(&path))
+            })
+            .collect();
+
+        let files = [(".config", core::CONFIG_HOME), ("fonts", LEGACY_FONTS_HOME)]
+            .into_iter()
+            .filter(|(source, _)| Path::new(&format!("{}/{}", desktop.get_path(), source)).is_dir())
+            .map(|(source, target)| (source.to_string(), target.to_string()))
+            .collect();
+
+        Manifest {
+            info: DesktopInfo::new(desktop),
+            patterns,
+            post_scripts: post_scripts
+                .into_iter()
+                .map(|(name, postscript)| (name, relative(postscript.get_path())))
+                .collect(),
+            extras,
+            files,
+        }
+    }
+}
+
+impl std::fmt::Display for Manifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", toml::to_string_pretty(self).unwrap())
+    }
+}
+
+// Paths in the home directory are written with `~`, so the manifest works for anyone
+fn collapse_home(path: &str) -> String {
+    let home = core::expand_path("~");
+    match path.strip_prefix(&home) {
+        Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let content = r#"
+            [info]
+            author = "me"
+            dependencies = ["kitty"]
+
+            [patterns.kitty]
+            path = "gtheme/patterns/kitty.pattern"
+            post-script = "gtheme/post-scripts/kitty.sh"
+
+            [files]
+            ".config" = "~/.config"
+        "#;
+        let manifest: Manifest = toml::from_str(content).unwrap();
+        assert_eq!(manifest.get_info().get_author(), "me");
+        assert_eq!(manifest.get_info().get_dependencies(), &vec!["kitty"]);
+        let kitty = &manifest.get_patterns()["kitty"];
+        assert_eq!(kitty.get_output(), &None);
+        assert_eq!(
+            kitty.get_post_script(),
+            &Some("gtheme/post-scripts/kitty.sh".to_string())
+        );
+        assert!(manifest.get_extras().is_empty());
+
+        let saved: Manifest = toml::from_str(&manifest.to_string()).unwrap();
+        assert_eq!(saved.get_files(), manifest.get_files());
+        assert_eq!(
+            Manifest::resolve("/desktop", "gtheme/a.sh"),
+            "/desktop/gtheme/a.sh"
+        );
+        assert_eq!(Manifest::resolve("/desktop", "/usr/bin/a"), "/usr/bin/a");
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let dir = std::env::temp_dir().join(format!("gtheme-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let desktop_path = dir.display().to_string();
+        assert!(matches!(Manifest::read(&desktop_path), Ok(None)));
+
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            "[files\n\".config\" = \"~/.config\"",
+        )
+        .unwrap();
+        assert!(Manifest::read(&desktop_path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
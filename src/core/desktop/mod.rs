@@ -1,14 +1,26 @@
-use std::collections::BTreeMap;
-use std::fs::{self, metadata, DirEntry};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, metadata};
 use std::path::{Path, PathBuf};
 
 use crate::core;
-use crate::core::config::{GlobalConfig, UserConfig};
+use crate::core::backup::Backup;
+use crate::core::config::{DesktopInfo, GlobalConfig, UserConfig};
+use crate::core::diagnostic;
+use crate::core::history::HistoryEntry;
 use crate::core::pattern::*;
-use crate::core::postscript::PostScript;
+use crate::core::postscript::{Environment, PostScript};
 use crate::core::theme::Theme;
+use crate::core::transaction::Transaction;
 
-use log::{error, info};
+use log::{error, info, warn};
+
+mod link;
+mod lockfile;
+mod manifest;
+
+pub use link::{get_state as get_link_state, InstallMode, LinkState};
+pub use lockfile::Lockfile;
+pub use manifest::{Manifest, PatternEntry, ReadManifest, LEGACY_FONTS_HOME, MANIFEST_FILE};
 
 #[derive(Debug, Clone)]
 pub struct Desktop {
@@ -17,18 +29,34 @@ pub struct Desktop {
     patterns: Vec<PatternFile>,
     post_scripts: BTreeMap<String, PostScript>,
     extras: Vec<PostScript>,
+    // Installed files, from a path inside the desktop to its target
+    files: BTreeMap<String, String>,
+    // Manifest read when the desktop was built, everything else comes from it
+    manifest: ReadManifest,
 }
 impl Desktop {
     pub fn from(desktop: &DesktopFile) -> Self {
-        let patterns = Pattern::get_patterns(desktop);
-        let post_scripts = PostScript::get_postscripts(desktop);
-        let extras = PostScript::get_extras(desktop);
+        let manifest = Manifest::read(desktop.get_path());
+        let patterns = Pattern::get_manifest_patterns(desktop, &manifest);
+        let post_scripts = PostScript::get_manifest_postscripts(desktop, &manifest);
+        let extras = PostScript::get_manifest_extras(desktop, &manifest);
+        let files = match &manifest {
+            Ok(Some(manifest)) => manifest.get_files().clone(),
+            // Nothing is installed until the manifest is fixed
+            Err(_) => BTreeMap::new(),
+            Ok(None) => BTreeMap::from([
+                (".config".to_string(), core::CONFIG_HOME.to_string()),
+                ("fonts".to_string(), LEGACY_FONTS_HOME.to_string()),
+            ]),
+        };
         Desktop {
             name: String::from(desktop.get_name()),
             path: String::from(desktop.get_path()),
             patterns,
             post_scripts,
             extras,
+            files,
+            manifest,
         }
     }
     pub fn get_name(&self) -> &String {
@@ -46,6 +74,9 @@ impl Desktop {
     pub fn get_extras(&self) -> &Vec<PostScript> {
         &self.extras
     }
+    pub fn get_files(&self) -> &BTreeMap<String, String> {
+        &self.files
+    }
 
     pub fn get_by_name(desktop: &str) -> Option<DesktopFile> {
         let all_desktops = Desktop::get_desktops();
@@ -122,55 +153,94 @@ impl Desktop {
         actived: &BTreeMap<String, bool>,
         inverted: &BTreeMap<String, bool>,
         dry_run: bool,
-    ) {
+        strict: bool,
+        force: bool,
+    ) -> bool {
+        if !self.check_manifest() {
+            return false;
+        }
         if dry_run {
             info!("Applying theme in dry-run mode...");
         }
 
-        let post_scripts = self.get_post_scripts();
+        let diagnostics = theme.check(Some(self));
+        diagnostics.iter().for_each(|diagnostic| diagnostic.log());
+        if diagnostic::has_errors(&diagnostics) {
+            if strict {
+                error!(
+                    "Theme |{}| has errors, refusing to apply it in strict mode",
+                    theme.get_name()
+                );
+                return false;
+            }
+            warn!(
+                "Theme |{}| has errors, some colors may be wrong or missing",
+                theme.get_name()
+            );
+        }
+
         info!(
             "Applying |{}| theme to |{}| desktop...",
             theme.get_name(),
             self.get_name()
         );
+        let mut transaction = Transaction::new();
         let user_config = UserConfig::new();
-        for pattern_file in self.get_patterns() {
-            let pattern = pattern_file.to_pattern();
+        let environment = Environment::new(theme, self.get_name(), dry_run, &user_config);
+        let is_actived = |name: &String| *actived.get(name).unwrap_or(&false);
+        let pending_postscripts = match self.fill_patterns(
+            theme,
+            is_actived,
+            inverted,
+            force,
+            &user_config,
+            &mut transaction,
+        ) {
+            Some(pending_postscripts) => {
+                with_environments(pending_postscripts, &environment, inverted)
+            }
+            None => {
+                transaction.rollback();
+                return false;
+            }
+        };
+        if !force {
+            transaction.discard_unchanged();
+        }
 
-            if !*actived.get(pattern.get_name()).unwrap_or(&false) {
-                continue;
+        //Dont write any file on dry-run mode
+        if dry_run {
+            transaction.rollback();
+        } else {
+            let history_entry = HistoryEntry::new(self, theme, actived, inverted, &transaction);
+            if !transaction.commit() {
+                error!(
+                    "Could not apply theme |{}|, previous files have been restored",
+                    theme.get_name()
+                );
+                return false;
             }
+            history_entry.save();
+        }
 
-            pattern.fill(
-                theme,
-                *inverted.get(pattern.get_name()).unwrap_or(&false),
-                &user_config,
-                dry_run,
-            );
-            if let Some(postscript) = post_scripts.get(pattern_file.get_name()) {
+        // Postscripts are only executed once every output has been written
+        //Dont execute postscripts on dry-run mode
+        let mut failures = Vec::new();
+        if dry_run {
+            for (postscript, _, _) in pending_postscripts {
                 info!("Executing |{}| post-script...", postscript.get_name());
-
-                //Dont execute postscripts on dry-run mode
-                if !dry_run {
-                    let output = pattern
-                        .get_output()
-                        .as_ref()
-                        .unwrap_or(&"".to_string())
-                        .clone();
-                    postscript.execute(&vec![output])
-                }
             }
+        } else {
+            failures = PostScript::execute_all(&pending_postscripts);
         }
 
-        let args_map = theme.get_extras();
         for extra_ps in self.get_extras() {
             if !*actived.get(extra_ps.get_name()).unwrap_or(&false) {
                 continue;
             }
 
-            let args = args_map
-                .get(extra_ps.get_name())
-                .unwrap_or(&vec![])
+            let args = extra_ps
+                .get_extra_args(theme)
                 .iter()
                 .map(|arg| core::expand_path(arg))
                 .collect();
@@ -178,19 +248,152 @@ impl Desktop {
             info!("Executing |{}| extra...", extra_ps.get_name());
 
             if !dry_run {
-                extra_ps.execute(&args);
+                if let Err(e) = extra_ps.execute(&args, &environment) {
+                    failures.push(e);
+                }
+            }
+        }
+        log_failures(&failures);
+        true
+    }
+
+    // Executes the post-scripts of the patterns writing any of the given outputs, so
+    // programs reload them after they were changed outside of a theme apply
+    pub fn execute_postscripts(
+        &self,
+        theme: &Theme,
+        outputs: &BTreeSet<String>,
+        inverted: &BTreeMap<String, bool>,
+    ) {
+        let post_scripts = self.get_post_scripts();
+        let pending_postscripts = self
+            .get_patterns()
+            .iter()
+            .filter_map(|pattern_file| {
+                let postscript = post_scripts.get(pattern_file.get_name())?;
+                let written: Vec<String> = get_outputs(pattern_file)
+                    .into_iter()
+                    .filter(|output| outputs.contains(output))
+                    .collect();
+                (!written.is_empty()).then_some((postscript, written))
+            })
+            .collect();
+
+        let user_config = UserConfig::new();
+        let environment = Environment::new(theme, self.get_name(), false, &user_config);
+        let failures = PostScript::execute_all(&with_environments(
+            pending_postscripts,
+            &environment,
+            inverted,
+        ));
+        log_failures(&failures);
+    }
+
+    // Fills the selected patterns into the transaction. Patterns are rendered concurrently
+    // but staged in order, stopping at the first failure. Returns the post-scripts to execute
+    // once the outputs are written, with the arguments each one receives. Unless forced,
+    // post-scripts of patterns whose outputs did not change are left out
+    fn fill_patterns(
+        &self,
+        theme: &Theme,
+        is_selected: impl Fn(&String) -> bool,
+        inverted: &BTreeMap<String, bool>,
+        force: bool,
+        user_config: &UserConfig,
+        transaction: &mut Transaction,
+    ) -> Option<Vec<(&PostScript, Vec<String>)>> {
+        let post_scripts = self.get_post_scripts();
+        let selected: Vec<&PatternFile> = self
+            .get_patterns()
+            .iter()
+            .filter(|pattern_file| is_selected(pattern_file.get_name()))
+            .collect();
+
+        let mut pending_postscripts = Vec::new();
+        for pattern in Pattern::render_all(&selected, theme, inverted, user_config) {
+            let stage_count = transaction.get_stage_count();
+            let pattern_name = pattern.get_name().clone();
+            let output = pattern.get_output().clone().unwrap_or_default();
+            if !pattern.stage(transaction) {
+                error!(
+                    "Could not fill pattern |{}|, aborting theme apply",
+                    pattern_name
+                );
+                return None;
+            }
+
+            if let Some(postscript) = post_scripts.get(&pattern_name) {
+                if !force && !transaction.has_changes_since(stage_count) {
+                    info!(
+                        "Skipping |{}| post-script, its output did not change",
+                        postscript.get_name()
+                    );
+                    continue;
+                }
+                pending_postscripts.push((postscript, vec![output]));
+            }
+        }
+        Some(pending_postscripts)
+    }
+
+    // Renders only the given patterns and executes the post-scripts of those whose outputs
+    // changed. Unlike apply_theme, extras are not executed and no history entry is saved
+    pub fn render_patterns(
+        &self,
+        theme: &Theme,
+        patterns: &BTreeSet<String>,
+        inverted: &BTreeMap<String, bool>,
+    ) -> bool {
+        let mut transaction = Transaction::new();
+        let user_config = UserConfig::new();
+        let environment = Environment::new(theme, self.get_name(), false, &user_config);
+        let pending_postscripts = match self.fill_patterns(
+            theme,
+            |name| patterns.contains(name),
+            inverted,
+            false,
+            &user_config,
+            &mut transaction,
+        ) {
+            Some(pending_postscripts) => {
+                with_environments(pending_postscripts, &environment, inverted)
+            }
+            None => {
+                transaction.rollback();
+                return false;
             }
+        };
+        transaction.discard_unchanged();
+        if !transaction.commit() {
+            error!("Could not write patterns, previous files have been restored");
+            return false;
         }
+
+        log_failures(&PostScript::execute_all(&pending_postscripts));
+        true
     }
 
+    // Removes the files recorded when the desktop was installed. Desktops installed before
+    // files were recorded lose every target instead, but only links in symlink mode
     pub fn clean_files(&self) {
-        let config_home = core::expand_path(core::CONFIG_HOME);
+        if let Some(lockfile) = Lockfile::read() {
+            if lockfile.get_desktop() == self.get_name() {
+                lockfile.uninstall();
+                return;
+            }
+        }
 
-        //Remove only config files, not fonts
+        // Remove only config files, not fonts
+        let fonts_home = PathBuf::from(core::expand_path(LEGACY_FONTS_HOME));
+        let copied = self.get_install_mode() == InstallMode::Copy;
         let to_remove = self
             .get_config_files()
             .into_iter()
-            .map(|entry| Path::new(&config_home).join(entry.file_name()))
+            .map(|(_, target)| target)
+            .filter(|target| !target.starts_with(&fonts_home))
+            .filter(|target| {
+                link::points_into(target, Path::new(self.get_path())) || (copied && target.exists())
+            })
             .collect::<Vec<_>>();
 
         if let Err(reason) = fs_extra::remove_items(&to_remove) {
@@ -198,6 +401,22 @@ impl Desktop {
         }
     }
 
+    // A desktop whose manifest cannot be read has no patterns nor files, so nothing is
+    // applied to it until the manifest is fixed
+    fn check_manifest(&self) -> bool {
+        if self.manifest.is_err() {
+            error!(
+                "Desktop |{}| has an invalid |{}|, fix it before applying",
+                self.get_name(),
+                MANIFEST_FILE
+            );
+            return false;
+        }
+        true
+    }
+
+    // Files the user had before gtheme are backed up first, and nothing is installed if
+    // that fails. Returns false in that case or when the theme could not be applied
     pub fn apply(
         &self,
         previous: &Option<Desktop>,
@@ -205,7 +424,10 @@ impl Desktop {
         actived: &BTreeMap<String, bool>,
         inverted: &BTreeMap<String, bool>,
         dry_run: bool,
-    ) {
+    ) -> bool {
+        if !self.check_manifest() {
+            return false;
+        }
         if dry_run {
             info!(
                 "Installing desktop |{}| in dry-run mode...",
@@ -215,43 +437,104 @@ impl Desktop {
             info!("Installing desktop |{}|...", self.get_name());
         }
 
-        let config_home = core::expand_path(core::CONFIG_HOME);
-        if let Some(previous_desktop) = previous {
-            info!("Uninstalling desktop |{}|...", previous_desktop.get_name());
-            if !dry_run {
-                previous_desktop.clean_files();
+        let unowned = self.get_unowned_targets(previous);
+        if !unowned.is_empty() {
+            info!(
+                "Backing up |{}| files not installed by gtheme...",
+                unowned.len()
+            );
+            if !dry_run && Backup::create(self.get_name(), &unowned).is_none() {
+                error!(
+                    "Could not back up existing files, desktop |{}| was not installed",
+                    self.get_name()
+                );
+                return false;
             }
         }
 
-        if !dry_run {
-            self.clean_files(); // Clean files to install
+        // Uninstalling keeps the files the user modified, installing does not overwrite them.
+        // Whole targets are only removed for desktops installed before the lockfile existed
+        let lockfile = Lockfile::read();
+        let modified: BTreeSet<String> = match &lockfile {
+            Some(lockfile) => lockfile.get_modified().into_iter().cloned().collect(),
+            None => BTreeSet::new(),
+        };
+        match (&lockfile, previous) {
+            (Some(lockfile), _) => {
+                info!("Uninstalling desktop |{}|...", lockfile.get_desktop());
+                if !dry_run {
+                    lockfile.uninstall();
+                }
+            }
+            (None, Some(previous_desktop)) => {
+                info!("Uninstalling desktop |{}|...", previous_desktop.get_name());
+                if !dry_run {
+                    previous_desktop.clean_files();
+                }
+            }
+            (None, None) => (),
         }
 
-        let config_files = self.get_config_files();
-        info!("Copying config files to |{}|...", &config_home);
-        for entry in config_files {
-            if dry_run {
-                break;
+        // Files are merged into existing directories, so user files next to them are kept
+        let install_mode = self.get_install_mode();
+        match install_mode {
+            InstallMode::Copy => {
+                // Links in place of the targets, like a dotfiles directory, were backed up as
+                // links. Installing through them would overwrite what they point to
+                for (_, target) in self.get_config_files() {
+                    if target.is_symlink() && !dry_run {
+                        if let Err(e) = fs::remove_file(&target) {
+                            error!("Could not remove link |{}|: |{}|", target.display(), e);
+                        }
+                    }
+                }
+                for (source, target) in self.get_installed_files() {
+                    if modified.contains(&target.display().to_string()) {
+                        warn!(
+                            "Keeping modified |{}|, the one of the desktop was not installed",
+                            target.display()
+                        );
+                        continue;
+                    }
+                    info!(
+                        "Copying |{}| to |{}|...",
+                        source.display(),
+                        target.display()
+                    );
+                    if dry_run {
+                        continue;
+                    }
+                    if target.is_symlink() {
+                        if let Err(e) = fs::remove_file(&target) {
+                            error!("Could not remove link |{}|: |{}|", target.display(), e);
+                            continue;
+                        }
+                    }
+                    core::install(&source, &target);
+                }
+            }
+            InstallMode::Symlink => {
+                for (source, target) in self.get_config_files() {
+                    info!(
+                        "Linking |{}| to |{}|...",
+                        target.display(),
+                        source.display()
+                    );
+                    if !dry_run {
+                        link::create(&source, &target);
+                    }
+                }
             }
-            let from = entry.path();
-            let to = Path::new(&config_home);
-            core::copy(&vec![from.as_path()], &to)
         }
-
-        let fonts_home = core::expand_path("~/.local/share/fonts/gtheme-fonts");
-        info!("Copying fonts to |{}|...", &fonts_home);
         if !dry_run {
-            let fonts_files = self.get_fonts_files();
-            let from_buf: Vec<PathBuf> =
-                fonts_files.into_iter().map(|entry| entry.path()).collect();
-            let from: Vec<&Path> = from_buf.iter().map(|buf| buf.as_path()).collect();
-
-            let to = Path::new(&fonts_home);
-            //Only copy if there is a fonts dir on desktop
-            core::copy(&from, &to);
+            Lockfile::new(self, install_mode, &modified).save();
         }
 
-        self.apply_theme(theme, actived, inverted, dry_run);
+        // Post-scripts of the new desktop always run, even if some outputs did not change.
+        // A failed theme is rolled back and already logged, the desktop is not applied
+        if !self.apply_theme(theme, actived, inverted, dry_run, false, true) {
+            return false;
+        }
 
         if let Some(previous_desktop) = previous {
             // Exit postcript from previous desktop
@@ -260,20 +543,116 @@ impl Desktop {
                 info!("Executing |desktop-exit| post-script");
                 // Dont execute exit postscript if dry-run mode
                 if !dry_run {
-                    ps.execute(&vec![])
+                    let environment = Environment::new(
+                        theme,
+                        previous_desktop.get_name(),
+                        dry_run,
+                        &UserConfig::new(),
+                    );
+                    // Failures are already logged, there is nothing left to apply
+                    let _ = ps.execute(&vec![], &environment);
                 };
             }
         };
+        true
     }
 
-    pub fn get_config_files(&self) -> Vec<DirEntry> {
-        let config_dir = format!("{}/.config", self.get_path());
-        return core::get_files(Path::new(&config_dir));
+    // Existing targets that hold anything gtheme did not install, or that the user modified.
+    // Without recorded files, everything the previous desktop maps is considered installed
+    fn get_unowned_targets(&self, previous: &Option<Desktop>) -> Vec<PathBuf> {
+        let lockfile = Lockfile::read();
+        let owned = match previous {
+            Some(previous_desktop) if lockfile.is_none() => previous_desktop
+                .get_config_files()
+                .into_iter()
+                .map(|(_, target)| target)
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.get_config_files()
+            .into_iter()
+            .map(|(_, target)| target)
+            .filter(|target| target.exists() || target.is_symlink())
+            .filter(|target| match &lockfile {
+                Some(lockfile) => !lockfile.owns(target),
+                None => !owned.iter().any(|owned| target.starts_with(owned)),
+            })
+            .collect()
     }
 
-    pub fn get_fonts_files(&self) -> Vec<DirEntry> {
-        let fonts_dir = format!("{}/fonts", self.get_path());
-        return core::get_files(Path::new(&fonts_dir));
+    // The `install_mode` user setting wins over the one of the desktop info
+    pub fn get_install_mode(&self) -> InstallMode {
+        if let Some(value) = UserConfig::new().get_properties().get("install_mode") {
+            match InstallMode::from(value) {
+                Some(install_mode) => return install_mode,
+                None => warn!(
+                    "Unknown install mode |{}| in user settings, expected |copy| or |symlink|",
+                    value
+                ),
+            }
+        }
+        let desktop = DesktopFile {
+            name: self.name.clone(),
+            path: self.path.clone(),
+        };
+        if !DesktopInfo::exists(&desktop) {
+            return InstallMode::default();
+        }
+        DesktopInfo::from_manifest(&desktop, &self.manifest)
+            .get_install_mode()
+            .unwrap_or_default()
+    }
+
+    // Every single file installed with the desktop and its target
+    pub fn get_installed_files(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut files = Vec::new();
+        for (source, target) in self.get_config_files() {
+            for file in lockfile::get_tree(&source) {
+                let relative = file.strip_prefix(&source).unwrap();
+                let file_target = if relative.as_os_str().is_empty() {
+                    target.clone()
+                } else {
+                    target.join(relative)
+                };
+                files.push((file, file_target));
+            }
+        }
+        files
+    }
+
+    // Every installed file and its target. Each entry of a mapped directory goes inside the
+    // target directory, while a mapped file is installed as the target itself. Missing
+    // sources are skipped, as legacy desktops may have no fonts
+    pub fn get_config_files(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut files = Vec::new();
+        for (source, target) in self.get_files() {
+            let source_path = PathBuf::from(Manifest::resolve(self.get_path(), source));
+            let target_path = PathBuf::from(core::expand_path(target));
+            if !target_path.is_absolute() {
+                warn!(
+                    "Skipping |{}|, its target |{}| is not an absolute path",
+                    source, target
+                );
+                continue;
+            }
+            if source_path.is_dir() {
+                for entry in core::get_files(&source_path) {
+                    files.push((entry.path(), target_path.join(entry.file_name())));
+                }
+            } else if source_path.exists() {
+                // A file would replace its target, so it cannot be a directory
+                let home = PathBuf::from(core::expand_path("~"));
+                if target_path == home || target_path == Path::new("/") || target_path.is_dir() {
+                    warn!(
+                        "Skipping |{}|, it is a file but its target |{}| is a directory",
+                        source, target
+                    );
+                    continue;
+                }
+                files.push((source_path, target_path));
+            }
+        }
+        files
     }
 
     pub fn add(from: &Path) -> Result<(), Option<DesktopFile>> {
@@ -377,6 +756,44 @@ impl Desktop {
     }
 }
 
+// Environment of each pattern post-script, named after its pattern
+fn with_environments<'a>(
+    postscripts: Vec<(&'a PostScript, Vec<String>)>,
+    environment: &Environment,
+    inverted: &BTreeMap<String, bool>,
+) -> Vec<(&'a PostScript, Vec<String>, Environment)> {
+    postscripts
+        .into_iter()
+        .map(|(postscript, args)| {
+            let pattern = postscript.get_name();
+            let is_inverted = *inverted.get(pattern).unwrap_or(&false);
+            let environment = environment.for_pattern(pattern, is_inverted);
+            (postscript, args, environment)
+        })
+        .collect()
+}
+
+// Outputs written by a pattern, or by its submodules
+fn get_outputs(pattern_file: &PatternFile) -> Vec<String> {
+    let pattern = pattern_file.to_pattern();
+    match pattern.get_submodules() {
+        Some(submodules) => submodules.iter().flat_map(get_outputs).collect(),
+        None => pattern.get_output().iter().cloned().collect(),
+    }
+}
+
+// Summary of the post-scripts and extras that failed. The theme is still applied, as every
+// output was already written
+fn log_failures(failures: &[String]) {
+    if !failures.is_empty() {
+        error!(
+            "|{}| post-scripts failed: |{}|",
+            failures.len(),
+            failures.join(", ")
+        );
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DesktopFile {
     name: String,
@@ -469,7 +886,7 @@ mod tests {
             desktop
                 .get_config_files()
                 .iter()
-                .map(|e| String::from(e.file_name().to_str().unwrap()))
+                .map(|(_, target)| target.display().to_string())
                 .collect::<Vec<String>>()
         );
     }
@@ -493,4 +910,73 @@ mod tests {
         let desktop = Desktop::get_by_name("test").unwrap();
         desktop.remove();
     }
+
+    #[test]
+    fn test_get_config_files() {
+        core::testing::with_home("mapping", |home| {
+            let desktop_dir = home.join(".config/gtheme/desktops/mapping");
+            fs::create_dir_all(desktop_dir.join("kitty")).unwrap();
+            for file in ["xinitrc", "Xresources", "picom.conf", "kitty/kitty.conf"] {
+                fs::write(desktop_dir.join(file), "").unwrap();
+            }
+            let manifest = r#"
+                [files]
+                "xinitrc" = "~"
+                "Xresources" = "~/.config"
+                "picom.conf" = "~/.config/picom/picom.conf"
+                "kitty" = "~/.config/kitty"
+            "#;
+            fs::write(desktop_dir.join(MANIFEST_FILE), manifest).unwrap();
+
+            let desktop = Desktop::get_by_name("mapping").unwrap().to_desktop();
+            let config = home.join(".config");
+            let targets: Vec<PathBuf> = desktop
+                .get_config_files()
+                .into_iter()
+                .map(|(_, target)| target)
+                .collect();
+            assert_eq!(
+                targets,
+                vec![
+                    config.join("kitty/kitty.conf"),
+                    config.join("picom/picom.conf")
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_get_config_files_sources() {
+        core::testing::with_home("mapping-sources", |home| {
+            let desktop_dir = home.join(".config/gtheme/desktops/mapping-sources");
+            fs::create_dir_all(desktop_dir.join("nvim/lua")).unwrap();
+            for file in ["bashrc", "nvim/init.vim", "nvim/lua/plugins.lua"] {
+                fs::write(desktop_dir.join(file), "").unwrap();
+            }
+            let manifest = r#"
+                [files]
+                "bashrc" = "~/.bashrc"
+                "nvim" = "~/.config/nvim"
+            "#;
+            fs::write(desktop_dir.join(MANIFEST_FILE), manifest).unwrap();
+
+            // Entries of a mapped directory go inside its target, a file is its target
+            let desktop = Desktop::get_by_name("mapping-sources")
+                .unwrap()
+                .to_desktop();
+            let mut files = desktop.get_config_files();
+            files.sort();
+            assert_eq!(
+                files,
+                vec![
+                    (desktop_dir.join("bashrc"), home.join(".bashrc")),
+                    (
+                        desktop_dir.join("nvim/init.vim"),
+                        home.join(".config/nvim/init.vim")
+                    ),
+                    (desktop_dir.join("nvim/lua"), home.join(".config/nvim/lua")),
+                ]
+            );
+        });
+    }
 }
@@ -0,0 +1,79 @@
+use log::{error, warn};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// Problem found in a gtheme file, with 1-based line and column numbers
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    severity: Severity,
+    path: String,
+    line: usize,
+    col: usize,
+    message: String,
+}
+
+impl Diagnostic {
+    pub fn error(path: &str, (line, col): (usize, usize), message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            path: path.to_string(),
+            line,
+            col,
+            message,
+        }
+    }
+    pub fn warning(path: &str, (line, col): (usize, usize), message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            path: path.to_string(),
+            line,
+            col,
+            message,
+        }
+    }
+
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+    pub fn get_col(&self) -> usize {
+        self.col
+    }
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub fn log(&self) {
+        match self.severity {
+            Severity::Error => error!("{}", self),
+            Severity::Warning => warn!("{}", self),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "|{}:{}:{}|: {}",
+            self.path, self.line, self.col, self.message
+        )
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|diagnostic| diagnostic.is_error())
+}
@@ -0,0 +1,432 @@
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::core;
+use crate::core::config::{DesktopConfig, GlobalConfig};
+use crate::core::desktop::Desktop;
+use crate::core::theme::Theme;
+use crate::core::transaction::Transaction;
+
+const MAX_HISTORY_ENTRIES: usize = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Backup {
+    path: String,
+    // File name inside the backups directory, None if the output did not exist before
+    backup: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    id: String,
+    date: String,
+    theme: String,
+    desktop: String,
+    actived: BTreeMap<String, bool>,
+    inverted: BTreeMap<String, bool>,
+    backups: Vec<Backup>,
+    #[serde(skip)]
+    contents: Vec<Option<Vec<u8>>>,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        desktop: &Desktop,
+        theme: &Theme,
+        actived: &BTreeMap<String, bool>,
+        inverted: &BTreeMap<String, bool>,
+        transaction: &Transaction,
+    ) -> Self {
+        let now = Local::now();
+        let mut backups = Vec::new();
+        let mut contents = Vec::new();
+        for (idx, (path, snapshot)) in transaction.get_snapshots().into_iter().enumerate() {
+            backups.push(Backup {
+                path: path.display().to_string(),
+                backup: snapshot.as_ref().map(|_| idx.to_string()),
+            });
+            contents.push(snapshot.clone());
+        }
+
+        HistoryEntry {
+            id: now.format("%Y%m%d%H%M%S%3f").to_string(),
+            date: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            theme: theme.get_name().to_string(),
+            desktop: desktop.get_name().to_string(),
+            actived: actived.clone(),
+            inverted: inverted.clone(),
+            backups,
+            contents,
+        }
+    }
+
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+    pub fn get_date(&self) -> &String {
+        &self.date
+    }
+    pub fn get_theme(&self) -> &String {
+        &self.theme
+    }
+    pub fn get_desktop(&self) -> &String {
+        &self.desktop
+    }
+    pub fn get_actived(&self) -> &BTreeMap<String, bool> {
+        &self.actived
+    }
+    pub fn get_inverted(&self) -> &BTreeMap<String, bool> {
+        &self.inverted
+    }
+    pub fn get_outputs(&self) -> Vec<&String> {
+        self.backups.iter().map(|backup| &backup.path).collect()
+    }
+
+    fn get_path(&self) -> PathBuf {
+        History::get_history_dir().join(&self.id)
+    }
+
+    pub fn save(&self) {
+        let entry_dir = self.get_path();
+        let backups_dir = entry_dir.join("backups");
+        if let Err(e) = fs::create_dir_all(&backups_dir) {
+            error!(
+                "Could not create history entry |{}|: |{}|",
+                entry_dir.display(),
+                e
+            );
+            return;
+        }
+
+        for (backup, content) in self.backups.iter().zip(self.contents.iter()) {
+            if let (Some(file_name), Some(bytes)) = (&backup.backup, content) {
+                if let Err(e) = fs::write(backups_dir.join(file_name), bytes) {
+                    error!("Could not backup |{}|: |{}|", backup.path, e);
+                    self.remove();
+                    return;
+                }
+            }
+        }
+
+        let content = serde_json::to_string_pretty(self).unwrap();
+        let path = entry_dir.join("entry.json");
+        let mut file = match OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+        {
+            Ok(f) => f,
+            Err(e) => {
+                error!("Could not open |{}|: |{}|", path.display(), e);
+                self.remove();
+                return;
+            }
+        };
+        match file.write_all(content.as_bytes()) {
+            Ok(_) => info!("Saving history entry |{}|...", self.get_id()),
+            Err(e) => {
+                error!(
+                    "Could not write history entry in |{}|: |{}|",
+                    path.display(),
+                    e
+                );
+                self.remove();
+            }
+        }
+        History::prune();
+    }
+
+    // Puts back every output as it was before this entry was applied
+    fn undo(&self) -> bool {
+        let backups_dir = self.get_path().join("backups");
+        let mut success = true;
+        for backup in self.backups.iter().rev() {
+            let path = Path::new(&backup.path);
+            let result = match &backup.backup {
+                Some(file_name) => {
+                    fs::read(backups_dir.join(file_name)).and_then(|bytes| fs::write(path, bytes))
+                }
+                None => match fs::remove_file(path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    result => result,
+                },
+            };
+            match result {
+                Ok(_) => info!("Restored |{}|", backup.path),
+                Err(e) => {
+                    error!("Could not restore |{}|: |{}|", backup.path, e);
+                    success = false;
+                }
+            }
+        }
+        if success {
+            self.remove();
+        }
+        success
+    }
+
+    fn remove(&self) {
+        if let Err(e) = fs::remove_dir_all(self.get_path()) {
+            error!(
+                "Could not remove history entry |{}|: |{}|",
+                self.get_id(),
+                e
+            );
+        }
+    }
+}
+
+// Theme applies, each with the outputs it overwrote. Files installed along with a desktop
+// are not recorded here, they are tracked by the lockfile and the backups of the desktop
+pub struct History;
+
+impl History {
+    fn get_history_dir() -> PathBuf {
+        Path::new(&core::expand_path(core::GTHEME_MISC)).join("history")
+    }
+
+    // Entries are returned from oldest to newest
+    pub fn get_entries() -> Vec<HistoryEntry> {
+        let mut entries = Vec::new();
+        for entry in core::get_files(&Self::get_history_dir()) {
+            let path = entry.path().join("entry.json");
+            let mut file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) => {
+                    warn!("Could not open history entry |{}|: |{}|", path.display(), e);
+                    continue;
+                }
+            };
+            let mut content = String::new();
+            if let Err(e) = file.read_to_string(&mut content) {
+                error!("Could not read history entry |{}|: |{}|", path.display(), e);
+                continue;
+            }
+            match serde_json::from_str::<HistoryEntry>(&content) {
+                Ok(history_entry) => entries.push(history_entry),
+                Err(e) => error!(
+                    "Could not parse history entry |{}|: |{}|",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        entries.sort_by(|a, b| a.get_id().cmp(b.get_id()));
+        entries
+    }
+
+    // Reverts the last apply
+    pub fn undo(global_config: &mut GlobalConfig) -> bool {
+        let entries = Self::get_entries();
+        if entries.is_empty() {
+            warn!("There is nothing to undo, |history is empty|");
+            return false;
+        }
+        Self::undo_until(entries.len() - 1, entries, global_config)
+    }
+
+    // Goes back to the state right after the specified entry was applied,
+    // reverting every later apply from newest to oldest
+    pub fn restore(id: &str, global_config: &mut GlobalConfig) -> bool {
+        let entries = Self::get_entries();
+        match entries.iter().position(|entry| entry.get_id() == id) {
+            Some(pos) if pos + 1 == entries.len() => {
+                info!("History entry |{}| is already the current state", id);
+                true
+            }
+            Some(pos) => Self::undo_until(pos + 1, entries, global_config),
+            None => {
+                error!("History entry |{}| does not exist", id);
+                false
+            }
+        }
+    }
+
+    fn undo_until(
+        first: usize,
+        mut entries: Vec<HistoryEntry>,
+        global_config: &mut GlobalConfig,
+    ) -> bool {
+        let current_desktop = match global_config.get_current_desktop() {
+            Some(desktop) => desktop.get_name().clone(),
+            None => {
+                error!("Cannot restore history, |there is no desktop installed!|");
+                return false;
+            }
+        };
+
+        if let Some(entry) = entries[first..]
+            .iter()
+            .find(|entry| *entry.get_desktop() != current_desktop)
+        {
+            error!(
+                "Cannot undo |{}|, it was applied on desktop |{}| but current desktop is |{}|",
+                entry.get_id(),
+                entry.get_desktop(),
+                current_desktop
+            );
+            return false;
+        }
+
+        let mut restored = BTreeSet::new();
+        while entries.len() > first {
+            let entry = entries.pop().unwrap();
+            restored.extend(entry.get_outputs().into_iter().cloned());
+            info!(
+                "Undoing |{}| theme applied on |{}|...",
+                entry.get_theme(),
+                entry.get_date()
+            );
+            if !entry.undo() {
+                return false;
+            }
+        }
+
+        let entry = match entries.last() {
+            Some(entry) if *entry.get_desktop() == current_desktop => entry,
+            _ => {
+                warn!("Could not know which theme was applied before, current theme is kept");
+                return true;
+            }
+        };
+
+        // Patterns and inverted patterns go back to the ones of the restored apply
+        let desktop_file = global_config.get_current_desktop().clone().unwrap();
+        let mut desktop_config = DesktopConfig::new(&desktop_file);
+        desktop_config
+            .get_mut_actived()
+            .extend(entry.get_actived().clone());
+        desktop_config
+            .get_mut_inverted()
+            .extend(entry.get_inverted().clone());
+        desktop_config.save();
+
+        match Theme::get_by_name(entry.get_theme()) {
+            Some(theme) => {
+                info!("Current theme is now |{}|", theme.get_name());
+                desktop_file.to_desktop().execute_postscripts(
+                    &theme.to_theme(),
+                    &restored,
+                    desktop_config.get_inverted(),
+                );
+                global_config.set_current_theme(theme);
+            }
+            None => warn!(
+                "Theme |{}| no longer exists, post-scripts were not executed",
+                entry.get_theme()
+            ),
+        }
+        true
+    }
+
+    fn prune() {
+        let entries = Self::get_entries();
+        if entries.len() <= MAX_HISTORY_ENTRIES {
+            return;
+        }
+        for entry in &entries[..entries.len() - MAX_HISTORY_ENTRIES] {
+            entry.remove();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::desktop::MANIFEST_FILE;
+    use crate::core::testing;
+    use std::os::unix::fs::PermissionsExt;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn test_undo_restore() {
+        testing::with_home("history", |home| {
+            let gtheme = home.join(".config/gtheme");
+            for (name, background) in [("first", "000000"), ("second", "ffffff")] {
+                let content = format!(
+                    "name = \"{}\"\n[colors]\nbackground = \"{}\"\nforeground = \"{}\"\n",
+                    name, background, background
+                );
+                fs::write(gtheme.join(format!("themes/{}.toml", name)), content).unwrap();
+            }
+            let desktop_dir = gtheme.join("desktops/history");
+            fs::create_dir_all(&desktop_dir).unwrap();
+            let output = home.join("colors.conf");
+            let log = home.join("post-script.log");
+            let manifest = format!(
+                "[patterns.kitty]\npath = \"kitty.pattern\"\noutput = \"{}\"\npost-script = \"kitty.sh\"\n",
+                output.display()
+            );
+            fs::write(desktop_dir.join(MANIFEST_FILE), manifest).unwrap();
+            fs::write(desktop_dir.join("kitty.pattern"), "bg <[background]>").unwrap();
+            let script = desktop_dir.join("kitty.sh");
+            fs::write(
+                &script,
+                format!("#!/bin/sh\n# gtheme: mode=sync\necho run >> {}\n", log.display()),
+            )
+            .unwrap();
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+            fs::write(
+                gtheme.join("global_config.json"),
+                r#"{"current_desktop": "history", "current_theme": null, "fav_themes": []}"#,
+            )
+            .unwrap();
+
+            let mut global_config = GlobalConfig::new();
+            let desktop_file = global_config.get_current_desktop().clone().unwrap();
+            let desktop = desktop_file.to_desktop();
+            let actived = BTreeMap::from([("kitty".to_string(), true)]);
+            let apply = |theme: &str, inverted: bool| {
+                let theme = Theme::get_by_name(theme).unwrap().to_theme();
+                let inverted = BTreeMap::from([("kitty".to_string(), inverted)]);
+                assert!(desktop.apply_theme(&theme, &actived, &inverted, false, false, false));
+                // Entries are identified by the time they were applied
+                thread::sleep(Duration::from_millis(5));
+            };
+            apply("first", false);
+            apply("second", true);
+            apply("first", false);
+            assert_eq!(History::get_entries().len(), 3);
+
+            assert!(History::undo(&mut global_config));
+            assert_eq!(fs::read_to_string(&output).unwrap(), "bg ffffff");
+            assert_eq!(
+                global_config
+                    .get_current_theme()
+                    .as_ref()
+                    .unwrap()
+                    .get_name(),
+                "second"
+            );
+            assert_eq!(
+                DesktopConfig::new(&desktop_file)
+                    .get_inverted()
+                    .get("kitty"),
+                Some(&true)
+            );
+            assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 4);
+
+            let first = History::get_entries()[0].get_id().clone();
+            assert!(History::restore(&first, &mut global_config));
+            assert_eq!(fs::read_to_string(&output).unwrap(), "bg 000000");
+            assert_eq!(
+                DesktopConfig::new(&desktop_file)
+                    .get_inverted()
+                    .get("kitty"),
+                Some(&false)
+            );
+            assert_eq!(History::get_entries().len(), 1);
+
+            // Undoing the first apply removes the output it created
+            assert!(History::undo(&mut global_config));
+            assert!(!output.exists());
+            assert!(!History::undo(&mut global_config));
+        });
+    }
+}
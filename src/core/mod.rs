@@ -4,11 +4,19 @@ use std::{
     io::{self, Write},
     path::Path,
 };
+pub mod backup;
 pub mod config;
+pub mod dependency;
 pub mod desktop;
+pub mod diagnostic;
+pub mod history;
+pub mod palette;
 pub mod pattern;
 pub mod postscript;
+pub mod template;
 pub mod theme;
+pub mod transaction;
+pub mod watch;
 
 pub const GTHEME_HOME: &str = "~/.config/gtheme";
 pub const GTHEME_MISC: &str = "~/.gtheme";
@@ -80,6 +88,74 @@ fn copy(from: &[&Path], to: &Path) {
     }
 }
 
+// Installs a file or directory at exactly `to`, merging directories that already exist
+fn install(from: &Path, to: &Path) -> bool {
+    let to_display = to.display();
+    let result = if from.is_dir() {
+        let mut options = fs_extra::dir::CopyOptions::new();
+        options.overwrite = true;
+        options.content_only = true;
+        fs::create_dir_all(to)
+            .map_err(|e| e.to_string())
+            .and_then(|_| fs_extra::dir::copy(from, to, &options).map_err(|e| e.to_string()))
+    } else {
+        let parent = to.parent().unwrap_or(Path::new("/"));
+        fs::create_dir_all(parent)
+            .and_then(|_| fs::copy(from, to))
+            .map_err(|e| e.to_string())
+    };
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            error!(
+                "Could not install |{}| into |{to_display}|: |{e}|",
+                from.display()
+            );
+            false
+        }
+    }
+}
+
+// Tests that read or write gtheme files run one at a time, each in its own home directory
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    static HOME_LOCK: Mutex<()> = Mutex::new(());
+
+    struct Home {
+        previous: Option<std::ffi::OsString>,
+        path: PathBuf,
+    }
+
+    impl Drop for Home {
+        fn drop(&mut self) {
+            match &self.previous {
+                Some(previous) => env::set_var("HOME", previous),
+                None => env::remove_var("HOME"),
+            }
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    pub fn with_home<F: FnOnce(&Path)>(name: &str, test: F) {
+        let _lock = HOME_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = env::temp_dir().join(format!("gtheme-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(path.join(".config/gtheme/themes")).unwrap();
+        fs::create_dir_all(path.join(".config/gtheme/desktops")).unwrap();
+        let home = Home {
+            previous: env::var_os("HOME"),
+            path: path.clone(),
+        };
+        env::set_var("HOME", &home.path);
+        test(&path);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // use super::pattern::Pattern;
@@ -138,6 +214,6 @@ mod tests {
         let mut inverted = BTreeMap::new();
         inverted.insert(String::from("polybar"), true);
 
-        desktop.apply_theme(&theme, &active, &inverted, false);
+        desktop.apply_theme(&theme, &active, &inverted, false, false, false);
     }
 }
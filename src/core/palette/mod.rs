@@ -0,0 +1,309 @@
+use image::GenericImageView;
+use log::{error, info};
+use std::collections::BTreeMap;
+
+use crate::core::theme::Mode;
+use crate::utils::color::Color;
+
+// Images are shrunk before clustering, the palette barely changes and it is much faster
+const SAMPLE_SIZE: u32 = 128;
+const CLUSTERS: usize = 16;
+const ITERATIONS: usize = 20;
+
+// Minimum contrast ratios against the background
+const TEXT_CONTRAST: f64 = 7.0;
+const ACCENT_CONTRAST: f64 = 4.5;
+
+// Accent colors and the hue they are expected to have
+const ACCENTS: [(&str, f64); 6] = [
+    ("red", 0.0),
+    ("yellow", 55.0),
+    ("green", 120.0),
+    ("cyan", 185.0),
+    ("blue", 220.0),
+    ("magenta", 300.0),
+];
+// A cluster is only used as an accent if its hue is this close to the expected one
+const MAX_HUE_DISTANCE: f64 = 35.0;
+
+// Dominant colors of an image and how many pixels each one covers, most common first
+pub fn extract(image_path: &str) -> Option<Vec<(Color, usize)>> {
+    let image = match image::open(image_path) {
+        Ok(image) => image,
+        Err(e) => {
+            error!("Could not open image |{}|: |{}|", image_path, e);
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    info!(
+        "Extracting palette from |{}| ({}x{})...",
+        image_path, width, height
+    );
+
+    let pixels: Vec<[f64; 3]> = image
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8()
+        .pixels()
+        .map(|pixel| [pixel[0] as f64, pixel[1] as f64, pixel[2] as f64])
+        .collect();
+    if pixels.is_empty() {
+        error!("Image |{}| is empty", image_path);
+        return None;
+    }
+    Some(k_means(&pixels, CLUSTERS))
+}
+
+// Deterministic k-means: the first centroid is the mean color and every next one is the
+// pixel farthest from the chosen ones, so the same image always gives the same palette
+fn k_means(pixels: &[[f64; 3]], k: usize) -> Vec<(Color, usize)> {
+    let distance =
+        |a: &[f64; 3], b: &[f64; 3]| (0..3).map(|idx| (a[idx] - b[idx]).powi(2)).sum::<f64>();
+
+    let mut centroids = vec![mean(pixels.iter())];
+    while centroids.len() < k.min(pixels.len()) {
+        let farthest = pixels
+            .iter()
+            .max_by(|a, b| {
+                let nearest = |pixel: &[f64; 3]| {
+                    centroids
+                        .iter()
+                        .map(|centroid| distance(pixel, centroid))
+                        .fold(f64::MAX, f64::min)
+                };
+                nearest(a).total_cmp(&nearest(b))
+            })
+            .unwrap();
+        centroids.push(*farthest);
+    }
+
+    let mut assignments = vec![0; pixels.len()];
+    for _ in 0..ITERATIONS {
+        let mut changed = false;
+        for (pixel, assignment) in pixels.iter().zip(assignments.iter_mut()) {
+            let nearest = (0..centroids.len())
+                .min_by(|a, b| {
+                    distance(pixel, &centroids[*a]).total_cmp(&distance(pixel, &centroids[*b]))
+                })
+                .unwrap();
+            if nearest != *assignment {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+        for (idx, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&[f64; 3]> = pixels
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assignment)| **assignment == idx)
+                .map(|(pixel, _)| pixel)
+                .collect();
+            if !members.is_empty() {
+                *centroid = mean(members.into_iter());
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut clusters: Vec<(Color, usize)> = centroids
+        .iter()
+        .enumerate()
+        .map(|(idx, centroid)| {
+            let size = assignments.iter().filter(|a| **a == idx).count();
+            let [r, g, b] = centroid.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+            (Color::from_rgb(r, g, b), size)
+        })
+        .filter(|(_, size)| *size > 0)
+        .collect();
+    clusters.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    clusters
+}
+
+fn mean<'a>(pixels: impl Iterator<Item = &'a [f64; 3]>) -> [f64; 3] {
+    let mut sum = [0.0; 3];
+    let mut count: f64 = 0.0;
+    for pixel in pixels {
+        (0..3).for_each(|idx| sum[idx] += pixel[idx]);
+        count += 1.0;
+    }
+    sum.map(|channel| channel / count.max(1.0))
+}
+
+// Maps the clusters to every standard theme key. Foreground and selection reach
+// TEXT_CONTRAST against the background, accents and their -hg variants ACCENT_CONTRAST
+pub fn to_theme_colors(clusters: &[(Color, usize)], mode: Mode) -> BTreeMap<String, String> {
+    let total: usize = clusters.iter().map(|(_, size)| size).sum();
+    let dominant = clusters
+        .first()
+        .map(|(color, _)| *color)
+        .unwrap_or_else(|| Color::from_rgb(128, 128, 128));
+    let (hue, saturation, _) = dominant.to_hsl();
+
+    // Background keeps the hue of the dominant color, but barely saturated
+    let background = match mode {
+        Mode::Dark => Color::from_hsl(hue, saturation.min(0.25), 0.10, 1.0),
+        Mode::Light => Color::from_hsl(hue, saturation.min(0.25), 0.94, 1.0),
+    };
+    let foreground_base = match mode {
+        Mode::Dark => Color::from_hsl(hue, saturation.min(0.15), 0.85, 1.0),
+        Mode::Light => Color::from_hsl(hue, saturation.min(0.15), 0.18, 1.0),
+    };
+    let foreground = ensure_contrast(foreground_base, &background, TEXT_CONTRAST, mode);
+
+    let mut colors = BTreeMap::new();
+    let mut insert = |key: &str, color: Color| {
+        colors.insert(key.to_string(), color.to_hex()[1..].to_string());
+    };
+    insert("background", background);
+    insert("foreground", foreground);
+    insert("cursor", foreground);
+    insert("selection-background", foreground);
+    insert("selection-foreground", background);
+
+    let (black, black_hg, white, white_hg) = match mode {
+        Mode::Dark => (
+            background.lighten(0.08),
+            background.lighten(0.25),
+            foreground.darken(0.08),
+            foreground.lighten(0.08),
+        ),
+        Mode::Light => (
+            foreground.lighten(0.1),
+            foreground.lighten(0.25),
+            background.darken(0.12),
+            background.darken(0.04),
+        ),
+    };
+    insert("black", black);
+    insert(
+        "black-hg",
+        ensure_contrast(black_hg, &background, 3.0, mode),
+    );
+    insert("white", white);
+    insert("white-hg", white_hg);
+
+    let average_saturation = clusters
+        .iter()
+        .map(|(color, size)| color.to_hsl().1 * *size as f64)
+        .sum::<f64>()
+        / total.max(1) as f64;
+    let accents = get_accents(clusters, average_saturation, mode);
+    for ((key, _), accent) in ACCENTS.iter().zip(accents) {
+        let accent = ensure_contrast(accent, &background, ACCENT_CONTRAST, mode);
+        let accent_hg = match mode {
+            Mode::Dark => accent.lighten(0.1),
+            Mode::Light => accent.darken(0.1),
+        };
+        insert(key, accent);
+        insert(
+            &format!("{}-hg", key),
+            ensure_contrast(accent_hg, &background, ACCENT_CONTRAST, mode),
+        );
+    }
+    colors
+}
+
+// Pairs every accent with a saturated cluster of similar hue, closest pairs first so each
+// cluster is used at most once. Accents without a cluster get a color with their hue and
+// the overall saturation of the image
+fn get_accents(clusters: &[(Color, usize)], saturation: f64, mode: Mode) -> Vec<Color> {
+    let hue_distance = |hue: f64, expected_hue: f64| {
+        let distance = (hue - expected_hue).rem_euclid(360.0);
+        distance.min(360.0 - distance)
+    };
+    let lightness = match mode {
+        Mode::Dark => 0.65,
+        Mode::Light => 0.40,
+    };
+
+    // (distance, accent index, cluster index) of every candidate pair
+    let mut pairs = Vec::new();
+    for (accent_idx, (_, expected_hue)) in ACCENTS.iter().enumerate() {
+        for (cluster_idx, (color, _)) in clusters.iter().enumerate() {
+            let (hue, saturation, lightness) = color.to_hsl();
+            let distance = hue_distance(hue, *expected_hue);
+            if saturation > 0.2 && lightness > 0.1 && lightness < 0.9 && distance < MAX_HUE_DISTANCE
+            {
+                pairs.push((distance, accent_idx, cluster_idx));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut found: Vec<Option<Color>> = vec![None; ACCENTS.len()];
+    let mut used = Vec::new();
+    for (_, accent_idx, cluster_idx) in pairs {
+        if found[accent_idx].is_none() && !used.contains(&cluster_idx) {
+            found[accent_idx] = Some(clusters[cluster_idx].0);
+            used.push(cluster_idx);
+        }
+    }
+
+    ACCENTS
+        .iter()
+        .zip(found)
+        .map(|((_, expected_hue), color)| match color {
+            Some(color) => {
+                let (hue, saturation, _) = color.to_hsl();
+                Color::from_hsl(hue, saturation.clamp(0.4, 0.8), lightness, 1.0)
+            }
+            None => Color::from_hsl(*expected_hue, saturation.clamp(0.4, 0.7), lightness, 1.0),
+        })
+        .collect()
+}
+
+// Moves the color away from the background lightness until the contrast is reached
+fn ensure_contrast(color: Color, background: &Color, min_contrast: f64, mode: Mode) -> Color {
+    let mut color = color;
+    for _ in 0..50 {
+        if color.contrast(background) >= min_contrast {
+            break;
+        }
+        color = match mode {
+            Mode::Dark => color.lighten(0.02),
+            Mode::Light => color.darken(0.02),
+        };
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_pixels() -> Vec<[f64; 3]> {
+        let mut pixels = vec![[20.0, 30.0, 60.0]; 600];
+        pixels.extend(vec![[200.0, 40.0, 40.0]; 200]);
+        pixels.extend(vec![[40.0, 160.0, 60.0]; 100]);
+        pixels
+    }
+
+    #[test]
+    fn test_k_means() {
+        let clusters = k_means(&get_pixels(), 3);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0], (Color::from_rgb(20, 30, 60), 600));
+        assert_eq!(clusters[2], (Color::from_rgb(40, 160, 60), 100));
+        assert_eq!(k_means(&get_pixels(), 3), clusters);
+    }
+
+    #[test]
+    fn test_theme_colors_contrast() {
+        let clusters = k_means(&get_pixels(), 3);
+        for mode in [Mode::Dark, Mode::Light] {
+            let colors = to_theme_colors(&clusters, mode);
+            assert_eq!(colors.len(), 21);
+            let get = |key: &str| Color::from_hex(&colors[key]).unwrap();
+            let background = get("background");
+            assert!(get("foreground").contrast(&background) >= TEXT_CONTRAST);
+            for (accent, _) in ACCENTS {
+                assert!(get(accent).contrast(&background) >= ACCENT_CONTRAST);
+                assert!(get(&format!("{}-hg", accent)).contrast(&background) >= ACCENT_CONTRAST);
+            }
+            let is_dark = background.luminance() < 0.5;
+            assert_eq!(is_dark, mode == Mode::Dark);
+        }
+    }
+}
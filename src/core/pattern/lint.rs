@@ -0,0 +1,197 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+
+use super::{Pattern, PatternFile};
+use crate::core;
+use crate::core::config::UserConfig;
+use crate::core::diagnostic::Diagnostic;
+use crate::core::template::{self, Template, TemplateWarning};
+use crate::core::theme::Theme;
+
+// Checks every pattern (and submodule) against a theme. Positions refer to the pattern files
+// as they are on disk, including the <[output-file]> header
+pub fn check(patterns: &[PatternFile], theme: &Theme, user_config: &UserConfig) -> Vec<Diagnostic> {
+    let values = Pattern::get_values(theme, false, user_config);
+    let mut diagnostics = Vec::new();
+    // Output path -> pattern file that writes it
+    let mut outputs: BTreeMap<String, String> = BTreeMap::new();
+
+    for pattern_file in get_pattern_files(patterns) {
+        let path = pattern_file.get_path();
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    (1, 1),
+                    format!("Could not read pattern: |{}|", e),
+                ));
+                continue;
+            }
+        };
+
+        let (content, headers) = take_headers(&content);
+        // An output declared in the desktop manifest wins over the header
+        let output = match pattern_file.get_declared_output() {
+            Some(output) => Some((output.clone(), (1, 1))),
+            None => headers
+                .first()
+                .map(|(output, position)| (core::expand_path(output), *position)),
+        };
+        match output {
+            None => diagnostics.push(Diagnostic::error(
+                path,
+                (1, 1),
+                "Missing output file (hint: <[output-file]>=/path/to/output/file)".to_string(),
+            )),
+            Some((output, position)) => match outputs.get(&output) {
+                Some(other) => diagnostics.push(Diagnostic::error(
+                    path,
+                    position,
+                    format!("Output |{}| is also written by |{}|", output, other),
+                )),
+                None => {
+                    outputs.insert(output, path.clone());
+                }
+            },
+        }
+        for (_, position) in headers.iter().skip(1) {
+            diagnostics.push(Diagnostic::error(
+                path,
+                *position,
+                "Duplicated <[output-file]> header, only the first one is used".to_string(),
+            ));
+        }
+
+        let template = match Template::parse(&content) {
+            Ok(template) => template,
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    template::get_line_col(&content, e.get_offset()),
+                    format!("Syntax error: |{}|", e.get_message()),
+                ));
+                continue;
+            }
+        };
+
+        let (_, warnings) = template.render(&values);
+        for warning in warnings {
+            let (offset, message) = match warning {
+                TemplateWarning::EmptyProperty { offset } => {
+                    (offset, "Empty property <[]>".to_string())
+                }
+                TemplateWarning::MissingProperty { key, offset } => (
+                    offset,
+                    format!(
+                        "Unresolved property |{}| with theme |{}|",
+                        key,
+                        theme.get_name()
+                    ),
+                ),
+                TemplateWarning::InvalidFilter { offset, message } => (offset, message),
+                TemplateWarning::DefaultValue { .. } => continue,
+            };
+            diagnostics.push(Diagnostic::error(
+                path,
+                template::get_line_col(&content, offset),
+                message,
+            ));
+        }
+    }
+    diagnostics.sort_by(|a, b| {
+        (a.get_path(), a.get_line(), a.get_col()).cmp(&(b.get_path(), b.get_line(), b.get_col()))
+    });
+    diagnostics
+}
+
+// Pattern files with submodules flattened
+fn get_pattern_files(patterns: &[PatternFile]) -> Vec<PatternFile> {
+    let mut files = Vec::new();
+    for pattern_file in patterns {
+        match pattern_file.to_pattern().get_submodules() {
+            Some(submodules) => files.extend(get_pattern_files(submodules)),
+            None => files.push(pattern_file.clone()),
+        }
+    }
+    files
+}
+
+// Output path of an <[output-file]> header and its position
+type Header = (String, (usize, usize));
+
+// Blanks out every <[output-file]> header, so the remaining offsets still match the file,
+// and returns their values and positions
+fn take_headers(content: &str) -> (String, Vec<Header>) {
+    let re = Regex::new(r"<\[output-file\]>=(.*)(\r\n|\r|\n)").unwrap();
+    let mut stripped = content.to_string();
+    let mut headers = Vec::new();
+    for captures in re.captures_iter(content) {
+        let header = captures.get(0).unwrap();
+        let value = captures.get(1).unwrap();
+        headers.push((
+            value.as_str().to_string(),
+            template::get_line_col(content, header.start()),
+        ));
+        stripped.replace_range(
+            header.start()..value.end(),
+            &" ".repeat(value.end() - header.start()),
+        );
+    }
+    (stripped, headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_headers() {
+        let content = "<[output-file]>=~/a\nbg=<[background]>\n<[output-file]>=~/b\n";
+        let (stripped, headers) = take_headers(content);
+        assert_eq!(stripped.len(), content.len());
+        assert_eq!(template::get_line_col(&stripped, 23), (2, 4));
+        assert_eq!(
+            headers,
+            vec![("~/a".to_string(), (1, 1)), ("~/b".to_string(), (3, 1))]
+        );
+        assert!(!stripped.contains("output-file"));
+    }
+
+    #[test]
+    fn test_check_declared_output() {
+        let dir = std::env::temp_dir().join(format!("gtheme-lint-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("kitty.pattern"), "background <[background]>\n").unwrap();
+        fs::write(
+            dir.join("alacritty.pattern"),
+            "<[output-file]>=/tmp/colors.conf\nbackground <[background]>\n",
+        )
+        .unwrap();
+        let pattern_file = |name: &str, output: Option<&str>| PatternFile {
+            name: name.to_string(),
+            path: dir.join(format!("{}.pattern", name)).display().to_string(),
+            output: output.map(String::from),
+        };
+        let theme: Theme =
+            toml::from_str("name = \"test\"\n[colors]\nbackground = \"000000\"").unwrap();
+        let user_config = UserConfig::default();
+
+        let declared = pattern_file("kitty", Some("/tmp/colors.conf"));
+        assert!(check(std::slice::from_ref(&declared), &theme, &user_config).is_empty());
+
+        let missing = check(&[pattern_file("kitty", None)], &theme, &user_config);
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].get_message().starts_with("Missing output file"));
+
+        let collision = check(
+            &[declared, pattern_file("alacritty", None)],
+            &theme,
+            &user_config,
+        );
+        assert_eq!(collision.len(), 1);
+        assert!(collision[0].get_message().contains("is also written by"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
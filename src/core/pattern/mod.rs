@@ -1,102 +1,198 @@
-use log::{error, info, warn};
-use regex::{Captures, Regex};
+use log::{error, log, Level};
+use regex::Regex;
 use std::collections::BTreeMap;
 use std::fs::{self, metadata, File};
 use std::io::prelude::*;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
 
 use crate::core;
 use crate::core::config::UserConfig;
-use crate::core::desktop::DesktopFile;
+use crate::core::desktop::{DesktopFile, Manifest, ReadManifest};
+use crate::core::diagnostic::Diagnostic;
+use crate::core::template::{self, Template, TemplateWarning};
 use crate::core::theme::Theme;
+use crate::core::transaction::Transaction;
+
+mod lint;
+
+// Patterns rendered at the same time at most
+const MAX_RENDER_WORKERS: usize = 8;
+
+static OUTPUT_FILE_RE: OnceLock<Regex> = OnceLock::new();
+
+// Message logged while rendering, kept until the pattern is staged so patterns rendered
+// concurrently still log in order
+type LogRecord = (Level, String);
+
+// Logs the records in the order they were kept
+fn emit(logs: &[LogRecord]) {
+    for (level, message) in logs {
+        log!(*level, "{}", message);
+    }
+}
+
+// Outputs of a pattern and its submodules, rendered in memory
+#[derive(Debug)]
+pub struct RenderedPattern {
+    name: String,
+    output: Option<String>,
+    outputs: Vec<(String, String)>,
+    logs: Vec<LogRecord>,
+    is_rendered: bool,
+}
+
+impl RenderedPattern {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+    // Output file of the pattern itself, patterns with submodules have none
+    pub fn get_output(&self) -> &Option<String> {
+        &self.output
+    }
+
+    // Logs what happened while rendering and stages every output. Nothing is staged if
+    // the pattern could not be rendered
+    pub fn stage(self, transaction: &mut Transaction) -> bool {
+        emit(&self.logs);
+        if !self.is_rendered {
+            return false;
+        }
+        self.outputs
+            .into_iter()
+            .all(|(output, content)| transaction.stage(&output, content))
+    }
+}
 
 #[derive(Debug)]
 pub struct Pattern {
     name: String,
     path: String,
     output: Option<String>,
+    // Content with the <[output-file]> header blanked in place, so offsets match the file
     content: Option<String>,
+    // Start and end of the header in `content`, with its line ending
+    header: Option<(usize, usize)>,
     submodules: Option<Vec<PatternFile>>,
 }
 impl Pattern {
     //TODO: From str or from PatternFile??
     pub fn from(pattern: &PatternFile) -> Self {
+        let mut logs = Vec::new();
+        let result = Self::load(pattern, &mut logs);
+        emit(&logs);
+        result
+    }
+
+    // Reads the pattern keeping what went wrong in `logs`, for patterns read concurrently
+    fn load(pattern: &PatternFile, logs: &mut Vec<LogRecord>) -> Self {
         let pattern_path = Path::new(pattern.get_path());
 
         let metadata = match metadata(pattern_path) {
             Ok(metadata) => metadata,
             Err(e) => {
-                error!(
-                    "Could not read metadata from |{}|: |{}|",
-                    pattern.get_path(),
-                    e
-                );
+                logs.push((
+                    Level::Error,
+                    format!(
+                        "Could not read metadata from |{}|: |{}|",
+                        pattern.get_path(),
+                        e
+                    ),
+                ));
                 return Self::default(pattern);
             }
         };
 
         if metadata.is_dir() {
-            let submodules = Self::get_patterns_from_path(pattern_path);
+            if pattern.get_declared_output().is_some() {
+                logs.push((
+                    Level::Warn,
+                    format!(
+                        "Ignoring output of pattern |{}|, each of its submodules declares its own",
+                        pattern.get_name()
+                    ),
+                ));
+            }
+            let submodules = Self::get_patterns_from_path(pattern_path, logs);
             return Pattern {
                 name: pattern.get_name().to_string(),
                 path: pattern.get_path().to_string(),
                 output: None,
                 content: None,
+                header: None,
                 submodules: Some(submodules),
             };
         } else if !metadata.is_file() {
-            error!(
-                "Pattern |{}| from |{}|is not a directory nor a file",
-                pattern.get_name(),
-                pattern.get_path()
-            );
+            logs.push((
+                Level::Error,
+                format!(
+                    "Pattern |{}| from |{}|is not a directory nor a file",
+                    pattern.get_name(),
+                    pattern.get_path()
+                ),
+            ));
             return Self::default(pattern);
         }
 
         let mut file = match File::open(pattern_path) {
             Ok(file) => file,
             Err(e) => {
-                error!(
-                    "Could not open pattern |{}| from |{}|: |{}|",
-                    pattern.get_name(),
-                    pattern.get_path(),
-                    e
-                );
+                logs.push((
+                    Level::Error,
+                    format!(
+                        "Could not open pattern |{}| from |{}|: |{}|",
+                        pattern.get_name(),
+                        pattern.get_path(),
+                        e
+                    ),
+                ));
                 return Self::default(pattern);
             }
         };
 
-        let re = Regex::new(r"<\[output-file\]>=(.*)(\r\n|\r|\n)").unwrap();
+        let re = OUTPUT_FILE_RE
+            .get_or_init(|| Regex::new(r"<\[output-file\]>=(.*)(\r\n|\r|\n)").unwrap());
 
         let mut content = String::new();
-        file.read_to_string(&mut content).expect(&format!(
-            "Error while reading pattern: {}",
-            pattern.get_path()
-        ));
-
         match file.read_to_string(&mut content) {
             Ok(_) => (),
             Err(e) => {
-                error!(
-                    "Error while reading pattern |{}| from |{}|: |{}|",
-                    pattern.get_name(),
-                    pattern.get_path(),
-                    e
-                );
+                logs.push((
+                    Level::Error,
+                    format!(
+                        "Error while reading pattern |{}| from |{}|: |{}|",
+                        pattern.get_name(),
+                        pattern.get_path(),
+                        e
+                    ),
+                ));
                 return Self::default(pattern);
             }
         }
-        let output_path = match re.captures(&content) {
+        let captures = re.captures(&content);
+        let output_path = match &captures {
+            _ if pattern.get_declared_output().is_some() => pattern.get_declared_output().clone(),
             Some(capture) => Some(core::expand_path(&capture[1])),
             None => None,
         };
-        content = String::from(re.replace(&content, ""));
+        let header = captures.map(|capture| {
+            let (header, value) = (capture.get(0).unwrap(), capture.get(1).unwrap());
+            (header.start(), value.end(), header.end())
+        });
+        // The header is blanked but keeps its line ending, so later lines keep their number
+        let header = header.map(|(start, value_end, end)| {
+            content.replace_range(start..value_end, &" ".repeat(value_end - start));
+            (start, end)
+        });
 
         Pattern {
             name: String::from(pattern.get_name()),
             path: String::from(pattern.get_path()),
             output: output_path,
             content: Some(content),
+            header,
             submodules: None,
         }
     }
@@ -125,10 +221,29 @@ impl Pattern {
             path: String::from(pattern.get_path()),
             output: None,
             content: None,
+            header: None,
             submodules: None,
         }
     }
 
+    // Content that is rendered, which leaves out the header line
+    fn get_template_content(&self) -> String {
+        let content = self.get_content().as_ref().unwrap();
+        match self.header {
+            Some((start, end)) => format!("{}{}", &content[..start], &content[end..]),
+            None => content.clone(),
+        }
+    }
+
+    // Line and column in the pattern file of an offset of the rendered content
+    fn get_line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = match self.header {
+            Some((start, end)) if offset >= start => offset + end - start,
+            _ => offset,
+        };
+        template::get_line_col(self.get_content().as_ref().unwrap(), offset)
+    }
+
     pub fn get_by_name(desktop: &DesktopFile, pattern: &str) -> Option<PatternFile> {
         let all_patterns = Pattern::get_patterns(desktop);
         match all_patterns
@@ -144,16 +259,47 @@ impl Pattern {
     }
 
     pub fn get_patterns(desktop: &DesktopFile) -> Vec<PatternFile> {
+        Self::get_manifest_patterns(desktop, &Manifest::read(desktop.get_path()))
+    }
+
+    // Patterns of a desktop whose manifest was already read
+    pub fn get_manifest_patterns(
+        desktop: &DesktopFile,
+        manifest: &ReadManifest,
+    ) -> Vec<PatternFile> {
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(_) => return Vec::new(),
+        };
+        if let Some(manifest) = manifest {
+            let mut patterns: Vec<PatternFile> = manifest
+                .get_patterns()
+                .iter()
+                .map(|(name, entry)| PatternFile {
+                    name: name.clone(),
+                    path: Manifest::resolve(desktop.get_path(), entry.get_path()),
+                    output: entry.get_output().as_ref().map(|o| core::expand_path(o)),
+                })
+                .collect();
+            patterns.sort_by_key(|pattern| pattern.get_name().to_lowercase());
+            return patterns;
+        }
         let patterns_dir = format!("{}/gtheme/patterns", desktop.get_path());
         let path = Path::new(&patterns_dir);
-        return Self::get_patterns_from_path(path);
+        let mut logs = Vec::new();
+        let patterns = Self::get_patterns_from_path(path, &mut logs);
+        emit(&logs);
+        patterns
     }
 
-    fn get_patterns_from_path(path: &Path) -> Vec<PatternFile> {
+    fn get_patterns_from_path(path: &Path, logs: &mut Vec<LogRecord>) -> Vec<PatternFile> {
         let entries = match fs::read_dir(path) {
             Ok(dir) => dir,
             Err(e) => {
-                error!("Could not read directory |{}|: |{}|", path.display(), e);
+                logs.push((
+                    Level::Error,
+                    format!("Could not read directory |{}|: |{}|", path.display(), e),
+                ));
                 return vec![];
             }
         };
@@ -163,11 +309,14 @@ impl Pattern {
             let entry = match entry {
                 Ok(entry) => entry,
                 Err(e) => {
-                    error!(
-                        "Error while reading entry from dir |{}|: |{}|",
-                        path.display(),
-                        e
-                    );
+                    logs.push((
+                        Level::Error,
+                        format!(
+                            "Error while reading entry from dir |{}|: |{}|",
+                            path.display(),
+                            e
+                        ),
+                    ));
                     continue;
                 }
             };
@@ -175,7 +324,11 @@ impl Pattern {
             let file_name = match entry.file_name().into_string() {
                 Ok(f) => f,
                 Err(_) => {
-                    error!("Error while converting OsString to String: |Invalid unicode data|");
+                    logs.push((
+                        Level::Error,
+                        "Error while converting OsString to String: |Invalid unicode data|"
+                            .to_string(),
+                    ));
                     continue;
                 }
             };
@@ -184,7 +337,10 @@ impl Pattern {
             let md = match metadata(&path) {
                 Ok(md) => md,
                 Err(err) => {
-                    error!("Could not read metadata from |{}|: |{}|", path, err);
+                    logs.push((
+                        Level::Error,
+                        format!("Could not read metadata from |{}|: |{}|", path, err),
+                    ));
                     continue;
                 }
             };
@@ -204,7 +360,11 @@ impl Pattern {
                 }
             };
 
-            vec.push(PatternFile { name, path });
+            vec.push(PatternFile {
+                name,
+                path,
+                output: None,
+            });
         }
         vec.sort_by(|a, b| {
             a.get_name()
@@ -214,103 +374,229 @@ impl Pattern {
         vec
     }
 
-    pub fn fill(&self, theme: &Theme, is_inverted: bool, user_config: &UserConfig, dry_run: bool) {
-        info!(
-            "Filling |{}| pattern with |{}| theme...",
-            self.get_name(),
-            theme.get_name()
-        );
+    // Reports unresolved properties, syntax errors and output problems of the given patterns
+    pub fn check(
+        patterns: &[PatternFile],
+        theme: &Theme,
+        user_config: &UserConfig,
+    ) -> Vec<Diagnostic> {
+        lint::check(patterns, theme, user_config)
+    }
+
+    pub fn fill(
+        &self,
+        theme: &Theme,
+        is_inverted: bool,
+        user_config: &UserConfig,
+        transaction: &mut Transaction,
+    ) -> bool {
+        self.render(theme, is_inverted, user_config)
+            .stage(transaction)
+    }
+
+    // Renders the pattern without writing nor logging anything
+    pub fn render(
+        &self,
+        theme: &Theme,
+        is_inverted: bool,
+        user_config: &UserConfig,
+    ) -> RenderedPattern {
+        let mut rendered = RenderedPattern {
+            name: self.get_name().clone(),
+            output: self.get_output().clone(),
+            outputs: Vec::new(),
+            logs: Vec::new(),
+            is_rendered: true,
+        };
+        rendered.is_rendered = self.render_into(theme, is_inverted, user_config, &mut rendered);
+        rendered
+    }
+
+    // Renders every pattern on a bounded pool of threads. Results keep the order of `patterns`
+    pub fn render_all(
+        patterns: &[&PatternFile],
+        theme: &Theme,
+        inverted: &BTreeMap<String, bool>,
+        user_config: &UserConfig,
+    ) -> Vec<RenderedPattern> {
+        let workers = thread::available_parallelism()
+            .map(|workers| workers.get())
+            .unwrap_or(1)
+            .min(MAX_RENDER_WORKERS)
+            .min(patterns.len());
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<RenderedPattern>>> =
+            Mutex::new(patterns.iter().map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let pattern_file = match patterns.get(idx) {
+                        Some(pattern_file) => pattern_file,
+                        None => break,
+                    };
+                    let is_inverted = *inverted.get(pattern_file.get_name()).unwrap_or(&false);
+                    // Errors reading the pattern are logged before the rendering ones
+                    let mut logs = Vec::new();
+                    let pattern = Pattern::load(pattern_file, &mut logs);
+                    let mut rendered = pattern.render(theme, is_inverted, user_config);
+                    logs.append(&mut rendered.logs);
+                    rendered.logs = logs;
+                    results.lock().unwrap()[idx] = Some(rendered);
+                });
+            }
+        });
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|rendered| rendered.unwrap())
+            .collect()
+    }
+
+    fn render_into(
+        &self,
+        theme: &Theme,
+        is_inverted: bool,
+        user_config: &UserConfig,
+        rendered: &mut RenderedPattern,
+    ) -> bool {
+        rendered.logs.push((
+            Level::Info,
+            format!(
+                "Filling |{}| pattern with |{}| theme...",
+                self.get_name(),
+                theme.get_name()
+            ),
+        ));
 
         //If there are submodules
         if let Some(submodules) = self.get_submodules() {
-            for submodule in submodules {
-                submodule
-                    .to_pattern()
-                    .fill(theme, is_inverted, user_config, dry_run);
-            }
-            return;
+            return submodules.iter().all(|submodule| {
+                Pattern::load(submodule, &mut rendered.logs).render_into(
+                    theme,
+                    is_inverted,
+                    user_config,
+                    rendered,
+                )
+            });
+        }
+
+        if self.get_content().is_none() {
+            rendered.logs.push((
+                Level::Error,
+                format!(
+                    "Pattern |{}| could not be read, cannot fill it",
+                    self.get_name()
+                ),
+            ));
+            return false;
         }
 
         // if pattern has no submodules (i.e, is a file)
-        let filled_content = self.fill_values(theme, is_inverted, user_config);
+        let filled_content =
+            match self.fill_values(theme, is_inverted, user_config, &mut rendered.logs) {
+                Some(content) => content,
+                None => return false,
+            };
 
         let output_path = match self.get_output() {
             Some(output_path) => output_path,
             None => {
-                error!("Pattern |{}| does not have output file specified (hint: <[output-file]>=/path/to/output/file)", self.get_name());
-                return;
+                rendered.logs.push((Level::Error, format!("Pattern |{}| does not have output file specified (hint: <[output-file]>=/path/to/output/file)", self.get_name())));
+                return false;
             }
         };
 
-        // Return if dry_run mode. i.e, dont write content to output path
-        if !dry_run {
-            if let Err(e) = core::write_content_to(&filled_content, Path::new(output_path)) {
-                error!("Could not create |{}|: |{}|", output_path, e);
-            }
-        }
+        // Content is only written to disk when the transaction is commited
+        rendered.outputs.push((output_path.clone(), filled_content));
+        true
     }
 
-    pub fn fill_values(
+    fn fill_values(
         &self,
         theme: &Theme,
         is_inverted: bool,
         user_config: &UserConfig,
-    ) -> String {
-        let pattern_content = self.get_content().as_ref().unwrap();
+        logs: &mut Vec<LogRecord>,
+    ) -> Option<String> {
+        let pattern_content = self.get_template_content();
         let pattern_name = self.get_name();
 
+        let template = match Template::parse(&pattern_content) {
+            Ok(template) => template,
+            Err(e) => {
+                let (line, col) = self.get_line_col(e.get_offset());
+                logs.push((
+                    Level::Error,
+                    format!(
+                        "Syntax error in pattern |{pattern_name}| at |line {line}, column {col}|: |{}|",
+                        e.get_message()
+                    ),
+                ));
+                return None;
+            }
+        };
+
+        let (result, warnings) =
+            template.render(&Self::get_values(theme, is_inverted, user_config));
+        for warning in warnings {
+            let record = match warning {
+                TemplateWarning::EmptyProperty { offset } => {
+                    let (line, col) = self.get_line_col(offset);
+                    (Level::Warn, format!("There is an empty property (<[]>) in pattern |{pattern_name}|: content |line {line}, column {col}|"))
+                }
+                TemplateWarning::MissingProperty { key, offset } => {
+                    let (line, col) = self.get_line_col(offset);
+                    (Level::Warn, format!("Could not fill property |{key}| in pattern |{pattern_name}|: content |line {line}, column {col}|"))
+                }
+                TemplateWarning::DefaultValue { key, value } => {
+                    (Level::Info, format!("Filled property |{key}| with default value |{value}| in pattern |{pattern_name}|"))
+                }
+                TemplateWarning::InvalidFilter { offset, message } => {
+                    let (line, col) = self.get_line_col(offset);
+                    (Level::Warn, format!("{message} in pattern |{pattern_name}|: content |line {line}, column {col}|"))
+                }
+            };
+            logs.push(record);
+        }
+        Some(result)
+    }
+
+    // Keys available to patterns: user settings, theme colors and the theme name
+    fn get_values(
+        theme: &Theme,
+        is_inverted: bool,
+        user_config: &UserConfig,
+    ) -> BTreeMap<String, String> {
         let mut extended_keys = BTreeMap::new();
 
-        extended_keys.extend(user_config.get_properties().clone().into_iter());
+        extended_keys.extend(user_config.get_properties().clone());
         // Ensure that user_config does not overwrites theme keys
-        extended_keys.extend(theme.get_colors().clone().into_iter());
+        extended_keys.extend(theme.get_colors().clone());
         extended_keys.insert("theme-name".to_string(), theme.get_name().to_string());
 
-        // default values can have alfanumeric,underscore and spaces. Keys can only have alfanumeric,underscores and -
-        let re = Regex::new(r"<\[((?:\w|-)+)?(?:\|([\w  -]*))?\]>").unwrap();
-        
-
-        let result =re.replace_all(pattern_content,|captured:&Captures|{
-
-			// Check if there is no property key on the match
-			let property = match captured.get(1) {
-				None => {
-					let whole_capture = captured.get(0).unwrap();
-					warn!("There is an empty property (<[]>) in pattern |{pattern_name}|: content |byte offset {}|",
-						whole_capture.start());
-					return whole_capture.as_str().to_string();
-				},
-				Some(value) => value.as_str()
-			};
-
-			// Invert background colors if needed
-			let property = Pattern::get_real_property(property,is_inverted);
-
-			if let Some(value) = extended_keys.get(property){
-				return value.to_string()
-			}else{
-				// Check for a default value for missing property
-				match captured.get(2){
-					Some(value) => {
-						let default_value = value.as_str();
-						info!("Filled property |{property}| with default value |{default_value}| in pattern |{pattern_name}|");
-						return default_value.to_string()
-					},
-					None=> {
-						let whole_capture = captured.get(0).unwrap();
-						warn!("Could not fill property |{property}| in pattern |{pattern_name}|: content |byte offset {}|",
-							whole_capture.start());
-						return whole_capture.as_str().to_string()
-					}
-				}
-			}
-		}).to_string();
-
-        return result;
+        // Invert background colors if needed
+        if is_inverted {
+            let original = extended_keys.clone();
+            for property in [
+                "foreground",
+                "background",
+                "selection-foreground",
+                "selection-background",
+            ] {
+                match original.get(Pattern::get_real_property(property, is_inverted)) {
+                    Some(value) => extended_keys.insert(property.to_string(), value.clone()),
+                    None => extended_keys.remove(property),
+                };
+            }
+        }
+        extended_keys
     }
 
     // This inverts background and foreground key colors.
-    fn get_real_property(property: &str, is_inverted: bool) -> &str {
+    pub fn get_real_property(property: &str, is_inverted: bool) -> &str {
         if is_inverted {
             match property {
                 "foreground" => "background",
@@ -330,6 +616,8 @@ pub struct PatternFile {
     name: String,
     // TODO: Change path type to PathBuf
     path: String,
+    // Output declared in the desktop manifest, which wins over the pattern header
+    output: Option<String>,
 }
 impl PatternFile {
     pub fn to_pattern(&self) -> Pattern {
@@ -341,9 +629,69 @@ impl PatternFile {
     pub fn get_path(&self) -> &String {
         &self.path
     }
+    pub fn get_declared_output(&self) -> &Option<String> {
+        &self.output
+    }
 }
 
-// #[cfg(test)]
-// mod tests{
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_positions() {
+        core::testing::with_home("pattern-header", |home| {
+            let path = home.join("kitty.pattern");
+            let content =
+                "# Kitty\n<[output-file]>=~/kitty.conf\nbackground <[background]>\nempty <[]>\n";
+            fs::write(&path, content).unwrap();
+            let pattern_file = PatternFile {
+                name: "kitty".to_string(),
+                path: path.to_str().unwrap().to_string(),
+                output: None,
+            };
+            let pattern = pattern_file.to_pattern();
+            assert_eq!(pattern.get_content().as_ref().unwrap().len(), content.len());
+
+            let theme_content = "name = \"Nord\"\n[colors]\nbackground = \"000000\"\n";
+            fs::write(home.join(".config/gtheme/themes/Nord.toml"), theme_content).unwrap();
+            let theme = Theme::get_by_name("Nord").unwrap().to_theme();
+            let rendered = pattern.render(&theme, false, &UserConfig::new());
+            assert!(rendered.is_rendered);
+            assert_eq!(
+                rendered.outputs,
+                vec![(
+                    core::expand_path("~/kitty.conf"),
+                    "# Kitty\nbackground 000000\nempty <[]>\n".to_string()
+                )]
+            );
+            let (_, message) = rendered
+                .logs
+                .iter()
+                .find(|(level, _)| *level == Level::Warn)
+                .unwrap();
+            assert!(message.contains("line 4, column 7"), "{}", message);
+        });
+    }
+
+    #[test]
+    fn test_render_all_logs() {
+        core::testing::with_home("pattern-logs", |home| {
+            let theme_content = "name = \"Nord\"\n[colors]\nbackground = \"000000\"\n";
+            fs::write(home.join(".config/gtheme/themes/Nord.toml"), theme_content).unwrap();
+            let theme = Theme::get_by_name("Nord").unwrap().to_theme();
+            let missing = PatternFile {
+                name: "missing".to_string(),
+                path: home.join("missing.pattern").to_str().unwrap().to_string(),
+                output: None,
+            };
 
-// }
+            let rendered =
+                Pattern::render_all(&[&missing], &theme, &BTreeMap::new(), &UserConfig::new());
+            assert!(!rendered[0].is_rendered);
+            let (level, message) = &rendered[0].logs[0];
+            assert_eq!(*level, Level::Error);
+            assert!(message.contains("Could not read metadata"), "{}", message);
+        });
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use crate::core::config::UserConfig;
+use crate::core::theme::Theme;
+
+// Variables exported to every post-script and extra, on top of the inherited environment:
+//  GTHEME_THEME              name of the applied theme
+//  GTHEME_DESKTOP            name of the desktop the script belongs to
+//  GTHEME_PATTERN            pattern the post-script belongs to (empty for extras)
+//  GTHEME_INVERTED           `1` if that pattern is inverted, `0` otherwise
+//  GTHEME_DRY_RUN            `1` on dry runs, `0` otherwise
+//  GTHEME_COLOR_<KEY>        every theme color, as written in the theme
+//  GTHEME_SETTING_<KEY>      every user setting
+// Keys are uppercased and any character other than a letter or digit becomes `_`, so
+// `selection-background` is exported as GTHEME_COLOR_SELECTION_BACKGROUND
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+    variables: BTreeMap<String, String>,
+}
+
+impl Environment {
+    pub fn new(theme: &Theme, desktop: &str, dry_run: bool, user_config: &UserConfig) -> Self {
+        let mut variables = BTreeMap::new();
+        variables.insert("GTHEME_THEME".to_string(), theme.get_name().clone());
+        variables.insert("GTHEME_DESKTOP".to_string(), desktop.to_string());
+        variables.insert("GTHEME_PATTERN".to_string(), String::new());
+        variables.insert("GTHEME_INVERTED".to_string(), flag(false));
+        variables.insert("GTHEME_DRY_RUN".to_string(), flag(dry_run));
+        for (key, value) in theme.get_colors() {
+            variables.insert(get_variable_name("COLOR", key), value.clone());
+        }
+        for (key, value) in user_config.get_properties() {
+            variables.insert(get_variable_name("SETTING", key), value.clone());
+        }
+        Environment { variables }
+    }
+
+    // Environment of the post-script of a pattern
+    pub fn for_pattern(&self, pattern: &str, inverted: bool) -> Self {
+        let mut environment = self.clone();
+        environment
+            .variables
+            .insert("GTHEME_PATTERN".to_string(), pattern.to_string());
+        environment
+            .variables
+            .insert("GTHEME_INVERTED".to_string(), flag(inverted));
+        environment
+    }
+
+    pub fn get_variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+}
+
+fn flag(value: bool) -> String {
+    String::from(if value { "1" } else { "0" })
+}
+
+fn get_variable_name(group: &str, key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("GTHEME_{}_{}", group, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_variable_name() {
+        assert_eq!(
+            get_variable_name("COLOR", "selection-background"),
+            "GTHEME_COLOR_SELECTION_BACKGROUND"
+        );
+        assert_eq!(
+            get_variable_name("SETTING", "font.size"),
+            "GTHEME_SETTING_FONT_SIZE"
+        );
+    }
+}
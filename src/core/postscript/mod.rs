@@ -1,14 +1,32 @@
-use log::error;
-use std::collections::BTreeMap;
+use log::{error, info, warn};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::process::{Command, Stdio};
+use std::path::Path;
+use std::time::Duration;
 
-use crate::core::desktop::DesktopFile;
+use crate::core::desktop::{Desktop, DesktopFile, Manifest, ReadManifest};
+use crate::core::theme::{Theme, ThemeFile};
+
+mod environment;
+mod process;
+mod storage;
+
+pub use environment::Environment;
+use process::Process;
+pub use storage::Storage;
+
+const STORAGE_FILE: &str = "storage.toml";
+
+// Time sync post-scripts (and those others depend on) are given to finish
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone)]
 pub struct PostScript {
     name: String,
     path: String,
+    // Argument storage of extras kept in their own folder, legacy single file extras
+    // take their arguments from the theme
+    storage: Option<String>,
 }
 
 impl PostScript {
@@ -18,8 +36,41 @@ impl PostScript {
     pub fn get_path(&self) -> &String {
         &self.path
     }
+    pub fn get_storage(&self) -> &Option<String> {
+        &self.storage
+    }
 
     pub fn get_postscripts(desktop: &DesktopFile) -> BTreeMap<String, PostScript> {
+        Self::get_manifest_postscripts(desktop, &Manifest::read(desktop.get_path()))
+    }
+
+    // Post-scripts of a desktop whose manifest was already read
+    pub fn get_manifest_postscripts(
+        desktop: &DesktopFile,
+        manifest: &ReadManifest,
+    ) -> BTreeMap<String, PostScript> {
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(_) => return BTreeMap::new(),
+        };
+        if let Some(manifest) = manifest {
+            // Post-scripts of patterns are named after them
+            let pattern_postscripts = manifest
+                .get_patterns()
+                .iter()
+                .filter_map(|(name, entry)| Some((name, entry.get_post_script().as_ref()?)));
+            return pattern_postscripts
+                .chain(manifest.get_post_scripts())
+                .map(|(name, path)| {
+                    let postscript = PostScript {
+                        name: name.clone(),
+                        path: Manifest::resolve(desktop.get_path(), path),
+                        storage: None,
+                    };
+                    (name.clone(), postscript)
+                })
+                .collect();
+        }
         let postscripts_dir = format!("{}/gtheme/post-scripts", desktop.get_path());
         let entries = match fs::read_dir(&postscripts_dir) {
             Ok(dir) => dir,
@@ -62,7 +113,14 @@ impl PostScript {
                 None => file_name,
                 Some((prefix, _)) => String::from(prefix),
             };
-            map.insert(name.clone(), PostScript { name, path });
+            map.insert(
+                name.clone(),
+                PostScript {
+                    name,
+                    path,
+                    storage: None,
+                },
+            );
         }
         map
     }
@@ -83,6 +141,32 @@ impl PostScript {
     }
 
     pub fn get_extras(desktop: &DesktopFile) -> Vec<PostScript> {
+        Self::get_manifest_extras(desktop, &Manifest::read(desktop.get_path()))
+    }
+
+    // Extras of a desktop whose manifest was already read
+    pub fn get_manifest_extras(desktop: &DesktopFile, manifest: &ReadManifest) -> Vec<PostScript> {
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(_) => return Vec::new(),
+        };
+        if let Some(manifest) = manifest {
+            return manifest
+                .get_extras()
+                .iter()
+                .filter_map(|(name, path)| {
+                    let path = Manifest::resolve(desktop.get_path(), path);
+                    if Path::new(&path).is_dir() {
+                        return Self::get_folder_extra(name.clone(), &path);
+                    }
+                    Some(PostScript {
+                        name: name.clone(),
+                        path,
+                        storage: None,
+                    })
+                })
+                .collect();
+        }
         let extras_dir = format!("{}/gtheme/extras", desktop.get_path());
 
         let entries = match fs::read_dir(&extras_dir) {
@@ -122,25 +206,608 @@ impl PostScript {
                 }
             };
 
+            if entry.path().is_dir() {
+                if let Some(extra) = Self::get_folder_extra(file_name, &path) {
+                    extras_vec.push(extra);
+                }
+                continue;
+            }
+
             let name = match file_name.rsplit_once(".") {
                 None => file_name,
                 Some((prefix, _)) => String::from(prefix),
             };
-            extras_vec.push(PostScript { name, path });
+            extras_vec.push(PostScript {
+                name,
+                path,
+                storage: None,
+            });
         }
         extras_vec
     }
 
-    pub fn execute(&self, args: &Vec<String>) {
-        match Command::new(self.get_path())
-            .stdout(Stdio::null())
-            .stdin(Stdio::null())
-            .stderr(Stdio::null())
-            .args(args)
-            .spawn()
-        {
-            Ok(_) => (),
-            Err(e) => error!("Could not execute file |{}|: |{}|", self.get_path(), e),
+    // Extra made of a folder with a `script` (with any extension) and its storage
+    fn get_folder_extra(name: String, dir: &str) -> Option<PostScript> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Could not read directory |{}|: |{}|", dir, e);
+                return None;
+            }
+        };
+        let script = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.is_file() && path.file_stem().is_some_and(|stem| stem == "script"));
+        match script {
+            Some(script) => Some(PostScript {
+                name,
+                path: script.to_string_lossy().to_string(),
+                storage: Some(format!("{}/{}", dir, STORAGE_FILE)),
+            }),
+            None => {
+                warn!("Extra |{}| has no script in |{}|, skipping it", name, dir);
+                None
+            }
+        }
+    }
+
+    // Arguments of an extra for a theme: those stored for the theme, then those in the
+    // theme file itself (legacy extras, or themes not migrated yet). Otherwise the same
+    // goes for each ancestor, so inherited arguments survive the migration. The stored
+    // default is the last resort
+    pub fn get_extra_args(&self, theme: &Theme) -> Vec<String> {
+        let storage = match &self.storage {
+            Some(path) => Storage::read(path).unwrap_or_default(),
+            None => Storage::default(),
+        };
+        let chain = std::iter::once(theme.get_name()).chain(theme.get_ancestors());
+        for theme_name in chain {
+            if let Some(args) = storage.get_theme_args(theme_name) {
+                return args.clone();
+            }
+            if let Some(args) = theme.get_extras().get(self.get_name()) {
+                if theme.get_extra_origin(self.get_name()) == theme_name {
+                    return args.clone();
+                }
+            }
+        }
+        storage.get_default().clone()
+    }
+
+    // Turns a legacy single file extra into a folder, its script keeps the extension
+    fn into_folder(self) -> Option<PostScript> {
+        if self.storage.is_some() {
+            return Some(self);
         }
+        let path = Path::new(&self.path);
+        let dir = path.with_file_name(&self.name);
+        let script = match path.extension() {
+            Some(extension) => dir.join("script").with_extension(extension),
+            None => dir.join("script"),
+        };
+        if let Err(e) = fs::create_dir(&dir) {
+            error!("Could not create directory |{}|: |{}|", dir.display(), e);
+            return None;
+        }
+        if let Err(e) = fs::rename(path, &script) {
+            error!(
+                "Could not move extra |{}| into |{}|: |{}|",
+                self.path,
+                script.display(),
+                e
+            );
+            return None;
+        }
+        Some(PostScript {
+            name: self.name,
+            path: script.to_string_lossy().to_string(),
+            storage: Some(dir.join(STORAGE_FILE).to_string_lossy().to_string()),
+        })
+    }
+
+    // Moves the extras tables of every theme into the storages of the desktop extras, so
+    // themes only keep the extras of other desktops. Arguments another desktop still reads
+    // from a theme are copied but kept in it. Legacy extras become folders. Returns the
+    // themes moved into each extra, or None if something could not be written
+    pub fn migrate_extras(
+        desktop: &DesktopFile,
+        dry_run: bool,
+    ) -> Option<BTreeMap<String, Vec<String>>> {
+        let read_manifest = Manifest::read(desktop.get_path());
+        let extras = PostScript::get_manifest_extras(desktop, &read_manifest);
+        let find_extra = |name: &str| {
+            extras
+                .iter()
+                .find(|extra| extra.get_name().to_lowercase() == name.to_lowercase())
+        };
+
+        // Extras of the other desktops, which may share a name with the ones migrated
+        let other_extras: Vec<(String, PostScript)> = Desktop::get_desktops()
+            .into_iter()
+            .filter(|other| other.get_name() != desktop.get_name())
+            .flat_map(|other| {
+                PostScript::get_extras(&other)
+                    .into_iter()
+                    .map(move |extra| (other.get_name().clone(), extra))
+            })
+            .collect();
+        // Desktop that still takes the arguments of an extra from a theme, if any
+        let get_reader = |name: &str, theme_name: &str| {
+            other_extras
+                .iter()
+                .find(|(_, extra)| {
+                    extra.get_name().to_lowercase() == name.to_lowercase()
+                        && match extra.get_storage() {
+                            Some(path) => Storage::read(path)
+                                .unwrap_or_default()
+                                .get_theme_args(theme_name)
+                                .is_none(),
+                            None => true,
+                        }
+                })
+                .map(|(desktop_name, _)| desktop_name)
+        };
+
+        // Arguments of each extra by theme, only those written in the theme itself
+        let mut migrated: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        let mut theme_extras: Vec<(ThemeFile, Vec<String>)> = Vec::new();
+        for theme_file in Theme::get_themes() {
+            let theme = theme_file.to_theme();
+            let mut names = Vec::new();
+            for (name, args) in theme.get_extras() {
+                if theme.get_extra_origin(name) != theme.get_name() {
+                    continue;
+                }
+                match find_extra(name) {
+                    Some(extra) => {
+                        migrated
+                            .entry(extra.get_name().clone())
+                            .or_default()
+                            .insert(theme.get_name().clone(), args.clone());
+                        match get_reader(name, theme.get_name()) {
+                            Some(reader) => info!(
+                                "Keeping extra |{}| in theme |{}|, desktop |{}| still reads it",
+                                name,
+                                theme.get_name(),
+                                reader
+                            ),
+                            None => names.push(name.clone()),
+                        }
+                    }
+                    None => info!(
+                        "Keeping extra |{}| in theme |{}|, it is not an extra of desktop |{}|",
+                        name,
+                        theme.get_name(),
+                        desktop.get_name()
+                    ),
+                }
+            }
+            if !names.is_empty() {
+                theme_extras.push((theme_file, names));
+            }
+        }
+        let summary = migrated
+            .iter()
+            .map(|(extra, themes)| (extra.clone(), themes.keys().cloned().collect()))
+            .collect();
+
+        let mut manifest = read_manifest.ok()?;
+        for extra in extras {
+            let themes = match migrated.remove(extra.get_name()) {
+                Some(themes) => themes,
+                None => continue,
+            };
+            info!(
+                "Moving arguments of |{}| themes into |{}| extra storage...",
+                themes.len(),
+                extra.get_name()
+            );
+            if dry_run {
+                continue;
+            }
+            let was_folder = extra.get_storage().is_some();
+            let extra = extra.into_folder()?;
+            let storage_path = extra.get_storage().clone().unwrap();
+            if let (Some(manifest), false) = (&mut manifest, was_folder) {
+                // The manifest declared the script, now it has to declare the folder
+                let dir = Path::new(&storage_path).parent().unwrap();
+                let dir = dir.strip_prefix(desktop.get_path()).unwrap_or(dir);
+                manifest.set_extra(extra.get_name(), &dir.to_string_lossy());
+                if !manifest.save(desktop) {
+                    return None;
+                }
+            }
+            let mut storage = Storage::read(&storage_path)?;
+            for (theme_name, args) in themes {
+                storage.set_theme_args(&theme_name, args);
+            }
+            if !storage.save(&storage_path) {
+                return None;
+            }
+        }
+
+        // Themes are only cleaned once every storage has been saved
+        for (theme_file, names) in theme_extras {
+            info!(
+                "Removing extras |{}| from theme |{}|...",
+                names.join(", "),
+                theme_file.get_name()
+            );
+            if !dry_run && !theme_file.remove_extras(&names) {
+                return None;
+            }
+        }
+        Some(summary)
+    }
+
+    // Settings declared in the leading comments of the script, one per line:
+    //  # gtheme: after = bspwm, polybar
+    //  # gtheme: mode = sync
+    //  # gtheme: timeout = 10
+    pub fn get_settings(&self) -> Settings {
+        // Binaries and unreadable scripts have no directives
+        let content = fs::read_to_string(self.get_path()).unwrap_or_default();
+        let directives: BTreeMap<String, String> = content
+            .lines()
+            .map(|line| line.trim())
+            .take_while(|line| line.is_empty() || line.starts_with('#'))
+            .filter_map(|line| line.trim_start_matches('#').trim().strip_prefix("gtheme:"))
+            .filter_map(|directive| directive.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        let mut settings = Settings::default();
+        for (key, value) in directives {
+            match key.as_str() {
+                "after" => {
+                    settings.after = value
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .filter(|name| !name.is_empty())
+                        .collect()
+                }
+                "mode" => match ExecutionMode::from(&value) {
+                    Some(mode) => settings.mode = mode,
+                    None => warn!(
+                        "Invalid mode |{}| in |{}|, expected |async| or |sync|",
+                        value,
+                        self.get_path()
+                    ),
+                },
+                "timeout" => match value.parse::<u64>() {
+                    Ok(seconds) => settings.timeout = Duration::from_secs(seconds),
+                    Err(_) => warn!(
+                        "Invalid timeout |{}| in |{}|, expected a number of seconds",
+                        value,
+                        self.get_path()
+                    ),
+                },
+                _ => warn!("Unknown directive |{}| in |{}|", key, self.get_path()),
+            }
+        }
+        settings
+    }
+
+    // Executes the script on its own, waiting for it only if it is sync
+    pub fn execute(&self, args: &Vec<String>, environment: &Environment) -> Result<(), String> {
+        let settings = self.get_settings();
+        let process = Process::spawn(self, args, environment, &settings)?;
+        match settings.get_mode() {
+            ExecutionMode::Sync => process.wait(),
+            ExecutionMode::Async => Ok(()),
+        }
+    }
+
+    // Executes post-scripts concurrently. A post-script declaring dependencies waits until
+    // those running in this same batch have finished. Sync post-scripts are waited for and
+    // their output logged, async ones are left running unless another one depends on them.
+    // Returns a description of every failure
+    pub fn execute_all(postscripts: &[(&PostScript, Vec<String>, Environment)]) -> Vec<String> {
+        let names: Vec<&String> = postscripts.iter().map(|(ps, _, _)| ps.get_name()).collect();
+        let settings: Vec<Settings> = postscripts
+            .iter()
+            .map(|(postscript, _, _)| postscript.get_settings())
+            .collect();
+        let dependencies: Vec<Vec<usize>> = postscripts
+            .iter()
+            .zip(&settings)
+            .map(|((postscript, _, _), settings)| {
+                settings
+                    .after
+                    .iter()
+                    .filter_map(|dependency| {
+                        let idx = names.iter().position(|name| *name == dependency);
+                        if idx.is_none() {
+                            info!(
+                                "Post-script |{}| does not need to wait for |{}|, it is not executed",
+                                postscript.get_name(),
+                                dependency
+                            );
+                        }
+                        idx
+                    })
+                    .collect()
+            })
+            .collect();
+        let waited: BTreeSet<usize> = dependencies.iter().flatten().copied().collect();
+
+        // Post-scripts run in waves, each one with those whose dependencies already finished
+        let mut failures = Vec::new();
+        let mut finished = vec![false; postscripts.len()];
+        while finished.iter().any(|finished| !finished) {
+            let mut wave: Vec<usize> = (0..postscripts.len())
+                .filter(|idx| !finished[*idx])
+                .filter(|idx| dependencies[*idx].iter().all(|dep| finished[*dep]))
+                .collect();
+            if wave.is_empty() {
+                wave = (0..postscripts.len())
+                    .filter(|idx| !finished[*idx])
+                    .collect();
+                let cycle: Vec<&str> = wave.iter().map(|idx| names[*idx].as_str()).collect();
+                warn!(
+                    "Dependency cycle between post-scripts |{}|, executing them at once",
+                    cycle.join(", ")
+                );
+            }
+
+            let mut processes = Vec::new();
+            for idx in &wave {
+                let (postscript, args, environment) = &postscripts[*idx];
+                info!("Executing |{}| post-script...", postscript.get_name());
+                match Process::spawn(postscript, args, environment, &settings[*idx]) {
+                    Ok(process) => processes.push((*idx, process)),
+                    Err(e) => failures.push(e),
+                }
+            }
+            for (idx, process) in processes {
+                if settings[idx].mode == ExecutionMode::Sync || waited.contains(&idx) {
+                    if let Err(e) = process.wait() {
+                        failures.push(e);
+                    }
+                }
+            }
+            wave.iter().for_each(|idx| finished[*idx] = true);
+        }
+        failures
+    }
+}
+
+// Whether gtheme waits for a post-script to finish
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutionMode {
+    // Fire and forget, output is discarded
+    Async,
+    // Waited for until the timeout, output is logged and a non-zero exit code is a failure
+    Sync,
+}
+
+impl ExecutionMode {
+    pub fn from(mode: &str) -> Option<ExecutionMode> {
+        match mode.to_lowercase().as_str() {
+            "async" => Some(ExecutionMode::Async),
+            "sync" => Some(ExecutionMode::Sync),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    after: Vec<String>,
+    mode: ExecutionMode,
+    timeout: Duration,
+}
+
+impl Settings {
+    pub fn get_after(&self) -> &Vec<String> {
+        &self.after
+    }
+    pub fn get_mode(&self) -> ExecutionMode {
+        self.mode
+    }
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            after: vec![],
+            mode: ExecutionMode::Async,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+
+    fn create_script(dir: &Path, name: &str, content: &str) -> PostScript {
+        let path = dir.join(format!("{}.sh", name));
+        fs::write(&path, content).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        PostScript {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            storage: None,
+        }
+    }
+
+    #[test]
+    fn test_execute_all_dependencies() {
+        let dir = env::temp_dir().join("gtheme-postscript-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("log");
+
+        let slow = create_script(
+            &dir,
+            "slow",
+            &format!("#!/bin/sh\nsleep 0.3\necho slow >> {}\n", log.display()),
+        );
+        let after = create_script(
+            &dir,
+            "after",
+            &format!(
+                "#!/bin/sh\n# gtheme: after = slow, missing\n\necho after $GTHEME_PATTERN $GTHEME_INVERTED >> {}\n",
+                log.display()
+            ),
+        );
+        assert_eq!(after.get_settings().get_after(), &vec!["slow", "missing"]);
+        assert_eq!(slow.get_settings(), Settings::default());
+
+        let failures = PostScript::execute_all(&[
+            (
+                &after,
+                vec![],
+                Environment::default().for_pattern("kitty", true),
+            ),
+            (&slow, vec![], Environment::default()),
+        ]);
+        assert!(failures.is_empty());
+        // Post-scripts nobody depends on are not waited for
+        for _ in 0..50 {
+            if fs::read_to_string(&log).unwrap_or_default().lines().count() == 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(fs::read_to_string(&log).unwrap(), "slow\nafter kitty 1\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_execute_all_sync() {
+        let dir = env::temp_dir().join("gtheme-postscript-sync-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let failing = create_script(
+            &dir,
+            "failing",
+            "#!/bin/sh\n# gtheme: mode = sync\necho out\necho err >&2\nexit 3\n",
+        );
+        let hanging = create_script(
+            &dir,
+            "hanging",
+            "#!/bin/sh\n# gtheme: mode = sync\n# gtheme: timeout = 1\nexec sleep 10\n",
+        );
+        let settings = hanging.get_settings();
+        assert_eq!(settings.get_mode(), ExecutionMode::Sync);
+        assert_eq!(settings.get_timeout(), Duration::from_secs(1));
+
+        let started = std::time::Instant::now();
+        let failures = PostScript::execute_all(&[
+            (&failing, vec![], Environment::default()),
+            (&hanging, vec![], Environment::default()),
+        ]);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            failures,
+            vec!["failing exited with code 3", "hanging timed out after 1s"]
+        );
+        assert!(failing.execute(&vec![], &Environment::default()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_timeout_kills_children() {
+        let dir = env::temp_dir().join("gtheme-postscript-timeout-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let alive = dir.join("alive");
+
+        // The script waits for a child instead of becoming it
+        let hanging = create_script(
+            &dir,
+            "hanging",
+            &format!(
+                "#!/bin/sh\n# gtheme: mode = sync\n# gtheme: timeout = 1\nsh -c 'sleep 2; touch {}'\n",
+                alive.display()
+            ),
+        );
+        let started = std::time::Instant::now();
+        let failures = PostScript::execute_all(&[(&hanging, vec![], Environment::default())]);
+        assert_eq!(failures, vec!["hanging timed out after 1s"]);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(1500));
+        assert!(!alive.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_inherited_extras() {
+        use crate::core::desktop::Desktop;
+        crate::core::testing::with_home("migrate", |home| {
+            let gtheme = home.join(".config/gtheme");
+            let extras = gtheme.join("desktops/test/gtheme/extras");
+            fs::create_dir_all(&extras).unwrap();
+            fs::write(extras.join("wallpaper.sh"), "#!/bin/sh\n").unwrap();
+            let parent = "# Parent theme\nname = \"Parent\"\n\n[extras]\nwallpaper = [\"parent.png\"]\n\n[colors]\nbackground = \"#000000\"\n";
+            fs::write(gtheme.join("themes/Parent.toml"), parent).unwrap();
+            fs::write(
+                gtheme.join("themes/Child.toml"),
+                "name = \"Child\"\ninherits = \"Parent\"\n\n[colors]\n",
+            )
+            .unwrap();
+
+            let desktop = Desktop::get_by_name("test").unwrap();
+            let migrated = PostScript::migrate_extras(&desktop, false).unwrap();
+            assert_eq!(migrated["wallpaper"], vec!["Parent"]);
+            assert_eq!(
+                fs::read_to_string(gtheme.join("themes/Parent.toml")).unwrap(),
+                "# Parent theme\nname = \"Parent\"\n\n\n[colors]\nbackground = \"#000000\"\n"
+            );
+
+            let wallpaper = PostScript::get_extra_by_name(&desktop, "wallpaper").unwrap();
+            assert!(wallpaper.get_storage().is_some());
+            let child = Theme::get_by_name("Child").unwrap().to_theme();
+            assert_eq!(wallpaper.get_extra_args(&child), vec!["parent.png"]);
+        });
+    }
+
+    #[test]
+    fn test_migrate_shared_extras() {
+        crate::core::testing::with_home("migrate-shared", |home| {
+            let gtheme = home.join(".config/gtheme");
+            for name in ["first", "second"] {
+                let extras = gtheme.join("desktops").join(name).join("gtheme/extras");
+                fs::create_dir_all(&extras).unwrap();
+                fs::write(extras.join("polybar.sh"), "#!/bin/sh\n").unwrap();
+            }
+            fs::write(
+                gtheme.join("themes/Nord.toml"),
+                "name = \"Nord\"\n\n[extras]\npolybar = [\"nord\"]\n\n[colors]\n",
+            )
+            .unwrap();
+
+            // Second still reads polybar from the theme, so it is only copied
+            let first = Desktop::get_by_name("first").unwrap();
+            let migrated = PostScript::migrate_extras(&first, false).unwrap();
+            assert_eq!(migrated["polybar"], vec!["Nord"]);
+            let nord = Theme::get_by_name("Nord").unwrap().to_theme();
+            assert_eq!(nord.get_extras()["polybar"], vec!["nord"]);
+            let polybar = PostScript::get_extra_by_name(&first, "polybar").unwrap();
+            assert!(polybar.get_storage().is_some());
+            assert_eq!(polybar.get_extra_args(&nord), vec!["nord"]);
+
+            // Once every desktop has its own copy the theme entry goes away
+            let second = Desktop::get_by_name("second").unwrap();
+            PostScript::migrate_extras(&second, false).unwrap();
+            let nord = Theme::get_by_name("Nord").unwrap().to_theme();
+            assert!(nord.get_extras().get("polybar").is_none());
+            for desktop in [&first, &second] {
+                let polybar = PostScript::get_extra_by_name(desktop, "polybar").unwrap();
+                assert_eq!(polybar.get_extra_args(&nord), vec!["nord"]);
+            }
+        });
     }
 }
@@ -0,0 +1,175 @@
+use log::{error, info};
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Environment, ExecutionMode, PostScript, Settings};
+
+// How often a running post-script is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// Output still buffered after the script exits is collected for at most this long. A sync
+// script leaving a daemon behind must redirect its output, or the daemon keeps the pipes open
+const OUTPUT_GRACE: Duration = Duration::from_millis(200);
+
+// A line of output and whether it was written to stderr
+type OutputLine = (bool, String);
+
+pub struct Process {
+    name: String,
+    child: Child,
+    timeout: Duration,
+    started: Instant,
+    // Only sync post-scripts have their output captured
+    output: Option<Receiver<OutputLine>>,
+}
+
+impl Process {
+    pub fn spawn(
+        postscript: &PostScript,
+        args: &Vec<String>,
+        environment: &Environment,
+        settings: &Settings,
+    ) -> Result<Process, String> {
+        let capture = settings.get_mode() == ExecutionMode::Sync;
+        let output_stdio = || {
+            if capture {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            }
+        };
+        let mut child = match Command::new(postscript.get_path())
+            .stdout(output_stdio())
+            .stdin(Stdio::null())
+            .stderr(output_stdio())
+            .args(args)
+            .envs(environment.get_variables())
+            // Its own process group, so whatever it started can be killed along with it
+            .process_group(0)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                error!(
+                    "Could not execute file |{}|: |{}|",
+                    postscript.get_path(),
+                    e
+                );
+                return Err(format!("{} could not be executed", postscript.get_name()));
+            }
+        };
+
+        let output = if capture {
+            let (sender, receiver) = mpsc::channel();
+            if let Some(stdout) = child.stdout.take() {
+                read_lines(stdout, false, sender.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                read_lines(stderr, true, sender);
+            }
+            Some(receiver)
+        } else {
+            None
+        };
+
+        Ok(Process {
+            name: postscript.get_name().clone(),
+            child,
+            timeout: settings.get_timeout(),
+            started: Instant::now(),
+            output,
+        })
+    }
+
+    // Waits for the post-script to exit, killing it once the timeout is over. Captured
+    // output is logged, as errors if the script failed
+    pub fn wait(mut self) -> Result<(), String> {
+        let result = match self.wait_status() {
+            Some(status) if status.success() => Ok(()),
+            Some(status) => match status.code() {
+                Some(code) => Err(format!("{} exited with code {}", self.name, code)),
+                None => Err(format!("{} was terminated by a signal", self.name)),
+            },
+            None => {
+                self.kill();
+                let _ = self.child.wait();
+                Err(format!(
+                    "{} timed out after {}s",
+                    self.name,
+                    self.timeout.as_secs()
+                ))
+            }
+        };
+
+        for (is_stderr, line) in self.get_output() {
+            if result.is_err() && is_stderr {
+                error!("|{}|: {}", self.name, line);
+            } else {
+                info!("|{}|: {}", self.name, line);
+            }
+        }
+        if let Err(e) = &result {
+            error!("Post-script |{}|", e);
+        }
+        result
+    }
+
+    // Kills the whole process group, scripts that do not `exec` leave children behind
+    // holding the pipes
+    fn kill(&mut self) {
+        let group = self.child.id() as libc::pid_t;
+        if unsafe { libc::kill(-group, libc::SIGKILL) } != 0 {
+            let _ = self.child.kill();
+        }
+    }
+
+    // Exit status, or None if the timeout was reached
+    fn wait_status(&mut self) -> Option<ExitStatus> {
+        loop {
+            match self.child.try_wait() {
+                Ok(Some(status)) => return Some(status),
+                Ok(None) => (),
+                Err(e) => {
+                    error!("Could not wait for post-script |{}|: |{}|", self.name, e);
+                    return None;
+                }
+            }
+            if self.started.elapsed() >= self.timeout {
+                return None;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn get_output(&mut self) -> Vec<OutputLine> {
+        let receiver = match &self.output {
+            Some(receiver) => receiver,
+            None => return vec![],
+        };
+        let deadline = Instant::now() + OUTPUT_GRACE;
+        let mut lines = Vec::new();
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match receiver.recv_timeout(remaining) {
+                Ok(line) => lines.push(line),
+                // Both pipes closed, or the grace period is over
+                Err(_) => break,
+            }
+        }
+        lines
+    }
+}
+
+// Sends every line of a pipe through the channel. Readers are detached, they finish once
+// everything holding the pipe open has exited
+fn read_lines(pipe: impl Read + Send + 'static, is_stderr: bool, sender: Sender<OutputLine>) {
+    thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if sender.send((is_stderr, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,95 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Arguments of an extra for each theme, kept next to its script in `storage.toml`:
+//  default = ["~/wallpapers/default.png"]
+//  [themes]
+//  Nord = ["~/wallpapers/nord.png"]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Storage {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    default: Vec<String>,
+    #[serde(default)]
+    themes: BTreeMap<String, Vec<String>>,
+}
+
+impl Storage {
+    // A missing storage is an empty one
+    pub fn read(path: &str) -> Option<Storage> {
+        if !Path::new(path).exists() {
+            return Some(Storage::default());
+        }
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Could not read extra storage |{}|: |{}|", path, e);
+                return None;
+            }
+        };
+        match toml::from_str(&content) {
+            Ok(storage) => Some(storage),
+            Err(e) => {
+                error!("Could not parse extra storage |{}|: |{}|", path, e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> bool {
+        let content = toml::to_string_pretty(self).unwrap();
+        match fs::write(path, content) {
+            Ok(_) => true,
+            Err(e) => {
+                error!("Could not save extra storage |{}|: |{}|", path, e);
+                false
+            }
+        }
+    }
+
+    pub fn get_default(&self) -> &Vec<String> {
+        &self.default
+    }
+    pub fn get_themes(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.themes
+    }
+
+    // Arguments stored for a theme, theme names are case insensitive like everywhere else
+    pub fn get_theme_args(&self, theme_name: &str) -> Option<&Vec<String>> {
+        self.themes
+            .iter()
+            .find(|(name, _)| name.to_lowercase() == theme_name.to_lowercase())
+            .map(|(_, args)| args)
+    }
+
+    pub fn set_theme_args(&mut self, theme_name: &str, args: Vec<String>) {
+        self.themes.insert(theme_name.to_string(), args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage() {
+        let content = "default = [\"a.png\"]\n\n[themes]\nNord = [\"nord.png\", \"fill\"]\n";
+        let mut storage: Storage = toml::from_str(content).unwrap();
+        assert_eq!(storage.get_default(), &vec!["a.png"]);
+        assert_eq!(
+            storage.get_theme_args("nord"),
+            Some(&vec!["nord.png".to_string(), "fill".to_string()])
+        );
+        assert_eq!(storage.get_theme_args("Dark"), None);
+
+        storage.set_theme_args("Dark", vec!["dark.png".to_string()]);
+        let saved = toml::to_string_pretty(&storage).unwrap();
+        assert_eq!(toml::from_str::<Storage>(&saved).unwrap(), storage);
+        assert_eq!(
+            toml::to_string_pretty(&Storage::default()).unwrap(),
+            "[themes]\n"
+        );
+    }
+}
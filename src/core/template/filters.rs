@@ -0,0 +1,124 @@
+use crate::utils::color::{self, Color};
+
+// Filters that can be chained after a key: <[key | filter | filter(arg1, arg2)]>
+// `resolve` looks up other keys, so colors can be used as arguments (i.e, mix(blue, 50%))
+pub fn apply(
+    name: &str,
+    value: &str,
+    args: &[String],
+    resolve: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    match name {
+        "upper" => {
+            check_args(name, args, 0)?;
+            Ok(value.to_uppercase())
+        }
+        "lower" => {
+            check_args(name, args, 0)?;
+            Ok(value.to_lowercase())
+        }
+        "trim" => {
+            check_args(name, args, 0)?;
+            Ok(value.trim().to_string())
+        }
+        "prefix" => {
+            check_args(name, args, 1)?;
+            Ok(format!("{}{}", args[0], value))
+        }
+        "suffix" => {
+            check_args(name, args, 1)?;
+            Ok(format!("{}{}", value, args[0]))
+        }
+        "replace" => {
+            check_args(name, args, 2)?;
+            Ok(value.replace(&args[0], &args[1]))
+        }
+        // Color formats
+        "hex" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.to_hex())
+        }
+        "hexa" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.to_hexa())
+        }
+        "bare" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.to_hex()[1..].to_string())
+        }
+        "argb" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.to_argb())
+        }
+        "rgb" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.to_rgb())
+        }
+        "rgba" => {
+            let color = parse_color(value)?;
+            match args {
+                [] => Ok(color.to_rgba()),
+                [alpha] => Ok(color.with_alpha(parse_amount(name, alpha)?).to_rgba()),
+                _ => Err(format!("Filter |{}| expects at most 1 argument", name)),
+            }
+        }
+        "floats" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.to_rgb_floats())
+        }
+        // Color transformations, they keep the notation of the input color
+        "alpha" | "lighten" | "darken" | "saturate" | "desaturate" => {
+            check_args(name, args, 1)?;
+            let color = parse_color(value)?;
+            let amount = parse_amount(name, &args[0])?;
+            let transformed = match name {
+                "alpha" => color.with_alpha(amount),
+                "lighten" => color.lighten(amount),
+                "darken" => color.darken(amount),
+                "saturate" => color.saturate(amount),
+                _ => color.desaturate(amount),
+            };
+            Ok(transformed.format_like(value))
+        }
+        "complement" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.complement().format_like(value))
+        }
+        "invert" => {
+            check_args(name, args, 0)?;
+            Ok(parse_color(value)?.invert().format_like(value))
+        }
+        "mix" => {
+            let (other, weight) = match args {
+                [other] => (other, 0.5),
+                [other, weight] => (other, parse_amount(name, weight)?),
+                _ => return Err(format!("Filter |{}| expects 1 or 2 arguments", name)),
+            };
+            let other = resolve(other).unwrap_or_else(|| other.to_string());
+            let color = parse_color(value)?;
+            Ok(color.mix(&parse_color(&other)?, weight).format_like(value))
+        }
+        _ => Err(format!("Unknown filter |{}|", name)),
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    Color::from_hex(value).ok_or(format!("Value |{}| is not a valid color", value))
+}
+
+fn parse_amount(name: &str, amount: &str) -> Result<f64, String> {
+    color::parse_fraction(amount)
+        .ok_or(format!("Invalid amount |{}| for filter |{}|", amount, name))
+}
+
+fn check_args(name: &str, args: &[String], expected: usize) -> Result<(), String> {
+    if args.len() != expected {
+        return Err(format!(
+            "Filter |{}| expects {} arguments but got {}",
+            name,
+            expected,
+            args.len()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,352 @@
+// Template language used by patterns. Everything lives inside <[ ]> delimiters:
+//
+//  <[key]>                          value of a theme color or user setting
+//  <[key|default]>                  value, or `default` if the key does not exist
+//  <[key | upper | default(none)]>  value passed through a chain of filters
+//  <[red | lighten(10) | rgba(0.8)]>  color filters, see utils::color
+//  <[if key]> ... <[else]> ... <[end]>           key exists
+//  <[if key == "value"]> ... <[end]>             key equals value (also !=, `if not key`)
+//  <[for color in colors]> <[color.name]>=<[color.value]> <[color.hg]> <[end]>
+//
+// Available groups for loops are `colors` (the 8 base colors with name, value, hg and index)
+// and `ansi` (the 16 terminal colors, normal ones first, with name, value and index).
+// Block tags alone on a line do not leave an empty line in the output.
+mod filters;
+mod parser;
+
+use std::collections::BTreeMap;
+
+pub const COLOR_GROUPS: [&str; 2] = ["colors", "ansi"];
+const BASE_COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+#[derive(Debug)]
+pub struct TemplateError {
+    offset: usize,
+    message: String,
+}
+impl TemplateError {
+    fn new(offset: usize, message: String) -> Self {
+        TemplateError { offset, message }
+    }
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+}
+
+#[derive(Debug)]
+pub enum TemplateWarning {
+    EmptyProperty { offset: usize },
+    MissingProperty { key: String, offset: usize },
+    DefaultValue { key: String, value: String },
+    InvalidFilter { offset: usize, message: String },
+}
+
+#[derive(Debug)]
+struct Filter {
+    name: String,
+    args: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Expression {
+    key: Option<String>,
+    filters: Vec<Filter>,
+    raw: String,
+    offset: usize,
+}
+
+#[derive(Debug)]
+enum Condition {
+    Exists {
+        key: String,
+        negated: bool,
+    },
+    Equals {
+        key: String,
+        value: String,
+        negated: bool,
+    },
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Expression(Expression),
+    If {
+        condition: Condition,
+        then_nodes: Vec<Node>,
+        else_nodes: Vec<Node>,
+    },
+    For {
+        variable: String,
+        group: String,
+        nodes: Vec<Node>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+struct RenderState<'a> {
+    values: &'a BTreeMap<String, String>,
+    scopes: Vec<(String, BTreeMap<String, String>)>,
+    warnings: Vec<TemplateWarning>,
+}
+
+impl Template {
+    pub fn parse(content: &str) -> Result<Template, TemplateError> {
+        Ok(Template {
+            nodes: parser::parse(content)?,
+        })
+    }
+
+    pub fn render(&self, values: &BTreeMap<String, String>) -> (String, Vec<TemplateWarning>) {
+        let mut state = RenderState {
+            values,
+            scopes: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut output = String::new();
+        Self::render_nodes(&self.nodes, &mut state, &mut output);
+        (output, state.warnings)
+    }
+
+    fn render_nodes(nodes: &[Node], state: &mut RenderState, output: &mut String) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push_str(text),
+                Node::Expression(expression) => {
+                    output.push_str(&Self::render_expression(expression, state))
+                }
+                Node::If {
+                    condition,
+                    then_nodes,
+                    else_nodes,
+                } => {
+                    if Self::evaluate(condition, state) {
+                        Self::render_nodes(then_nodes, state, output)
+                    } else {
+                        Self::render_nodes(else_nodes, state, output)
+                    }
+                }
+                Node::For {
+                    variable,
+                    group,
+                    nodes,
+                } => {
+                    for item in Self::get_group(group, state.values) {
+                        state.scopes.push((variable.clone(), item));
+                        Self::render_nodes(nodes, state, output);
+                        state.scopes.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_expression(expression: &Expression, state: &mut RenderState) -> String {
+        let key = match &expression.key {
+            Some(key) => key,
+            None => {
+                state.warnings.push(TemplateWarning::EmptyProperty {
+                    offset: expression.offset,
+                });
+                return expression.raw.clone();
+            }
+        };
+
+        let mut value = Self::lookup(key, state);
+        for filter in &expression.filters {
+            if filter.name == "default" {
+                if value.is_none() {
+                    let default_value = filter.args.first().cloned().unwrap_or_default();
+                    state.warnings.push(TemplateWarning::DefaultValue {
+                        key: key.clone(),
+                        value: default_value.clone(),
+                    });
+                    value = Some(default_value);
+                }
+                continue;
+            }
+            // Filters are skipped over missing values, so a later default can still apply
+            let current = match &value {
+                Some(current) => current,
+                None => continue,
+            };
+            let result = filters::apply(&filter.name, current, &filter.args, &|key| {
+                Self::lookup(key, state)
+            });
+            match result {
+                Ok(filtered) => value = Some(filtered),
+                Err(message) => {
+                    state.warnings.push(TemplateWarning::InvalidFilter {
+                        offset: expression.offset,
+                        message,
+                    });
+                    return expression.raw.clone();
+                }
+            }
+        }
+
+        match value {
+            Some(value) => value,
+            None => {
+                state.warnings.push(TemplateWarning::MissingProperty {
+                    key: key.clone(),
+                    offset: expression.offset,
+                });
+                expression.raw.clone()
+            }
+        }
+    }
+
+    fn evaluate(condition: &Condition, state: &RenderState) -> bool {
+        match condition {
+            Condition::Exists { key, negated } => Self::lookup(key, state).is_some() != *negated,
+            Condition::Equals {
+                key,
+                value,
+                negated,
+            } => (Self::lookup(key, state).as_ref() == Some(value)) != *negated,
+        }
+    }
+
+    // Loop variables shadow properties. `var` alone is the same as `var.value`
+    fn lookup(key: &str, state: &RenderState) -> Option<String> {
+        let (variable, field) = key.split_once('.').unwrap_or((key, "value"));
+        if let Some((_, item)) = state.scopes.iter().rev().find(|(name, _)| name == variable) {
+            return item.get(field).cloned();
+        }
+        state.values.get(key).cloned()
+    }
+
+    fn get_group(group: &str, values: &BTreeMap<String, String>) -> Vec<BTreeMap<String, String>> {
+        let mut items = Vec::new();
+        let mut push_item = |fields: Vec<(&str, Option<&String>)>| {
+            let item = fields
+                .into_iter()
+                .filter_map(|(field, value)| value.map(|value| (field.to_string(), value.clone())))
+                .collect();
+            items.push(item);
+        };
+
+        match group {
+            "colors" => {
+                for (index, color) in BASE_COLORS.iter().enumerate() {
+                    let name = color.to_string();
+                    let index = index.to_string();
+                    push_item(vec![
+                        ("name", Some(&name)),
+                        ("value", values.get(*color)),
+                        ("hg", values.get(&format!("{}-hg", color))),
+                        ("index", Some(&index)),
+                    ]);
+                }
+            }
+            "ansi" => {
+                let names = BASE_COLORS
+                    .iter()
+                    .map(|color| color.to_string())
+                    .chain(BASE_COLORS.iter().map(|color| format!("{}-hg", color)));
+                for (index, name) in names.enumerate() {
+                    let index = index.to_string();
+                    push_item(vec![
+                        ("name", Some(&name)),
+                        ("value", values.get(&name)),
+                        ("index", Some(&index)),
+                    ]);
+                }
+            }
+            _ => (),
+        }
+        items
+    }
+}
+
+// Converts a byte offset into 1-based line and column numbers
+pub fn get_line_col(content: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(content.len());
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+    let col = before[line_start..].chars().count() + 1;
+    (line, col)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(content: &str) -> (String, Vec<TemplateWarning>) {
+        let mut values = BTreeMap::new();
+        values.insert("background".to_string(), "2e3440".to_string());
+        values.insert("red".to_string(), "bf616a".to_string());
+        values.insert("red-hg".to_string(), "d08770".to_string());
+        values.insert("font".to_string(), "Fira Code".to_string());
+        Template::parse(content).unwrap().render(&values)
+    }
+
+    #[test]
+    fn test_legacy_syntax() {
+        let (output, warnings) = render("bg=#<[background]> x=<[missing|some default]> <[]>");
+        assert_eq!(output, "bg=#2e3440 x=some default <[]>");
+        assert_eq!(warnings.len(), 2);
+
+        let (output, _) = render("<[missing]> <[not a key]>");
+        assert_eq!(output, "<[missing]> <[not a key]>");
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let content = "<[if font]>\nfont=<[font]>\n<[else]>\nfont=mono\n<[end]>\nend\n";
+        assert_eq!(render(content).0, "font=Fira Code\nend\n");
+
+        let content = "<[if font == \"Fira Code\"]>yes<[else]>no<[end]>";
+        assert_eq!(render(content).0, "yes");
+        let content = "<[if not missing]>yes<[end]><[if font != 'Fira Code']>no<[end]>";
+        assert_eq!(render(content).0, "yes");
+    }
+
+    #[test]
+    fn test_loops_and_filters() {
+        let content =
+            "<[for c in colors]><[if c.value]><[c.name]>=<[c | upper]>,<[c.hg]>;<[end]><[end]>";
+        assert_eq!(render(content).0, "red=BF616A,d08770;");
+
+        let content = "<[for c in ansi]><[if c]><[c.index]> <[end]><[end]>";
+        assert_eq!(render(content).0, "1 9 ");
+
+        let (output, _) = render("<[missing | upper | default(\"a|b\")]>");
+        assert_eq!(output, "a|b");
+    }
+
+    #[test]
+    fn test_color_filters() {
+        assert_eq!(render("<[red | rgba(0.8)]>").0, "rgba(191, 97, 106, 0.8)");
+        assert_eq!(render("<[red | alpha(50%) | argb]>").0, "0x80BF616A");
+        assert_eq!(render("#<[background | lighten(10)]>").0, "#434c5e");
+        assert_eq!(render("<[red | mix(background, 0)]>").0, "bf616a");
+        assert_eq!(render("<[red | mix(#000000, 100%) | hex]>").0, "#000000");
+
+        let (output, warnings) = render("<[font | lighten(10)]>");
+        assert_eq!(output, "<[font | lighten(10)]>");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(Template::parse("<[if font]>no end").is_err());
+        assert!(Template::parse("text <[end]>").is_err());
+        assert!(Template::parse("<[for c in unknown]><[end]>").is_err());
+
+        let error = Template::parse("a\nb <[else]>").unwrap_err();
+        assert_eq!(get_line_col("a\nb <[else]>", error.get_offset()), (2, 3));
+    }
+}
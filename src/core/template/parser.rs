@@ -0,0 +1,361 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::{Condition, Expression, Filter, Node, TemplateError, COLOR_GROUPS};
+
+const OPEN_DELIMITER: &str = "<[";
+const CLOSE_DELIMITER: &str = "]>";
+
+// Compiled once, templates use them for every tag
+static CONDITION_RE: OnceLock<Regex> = OnceLock::new();
+static LEGACY_RE: OnceLock<Regex> = OnceLock::new();
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Tag {
+        body: String,
+        raw: String,
+        offset: usize,
+    },
+}
+
+#[derive(Debug)]
+enum Block {
+    If,
+    Else,
+    End,
+    For,
+}
+
+fn get_block(body: &str) -> Option<Block> {
+    let keyword = body.split_whitespace().next().unwrap_or("");
+    match keyword {
+        "if" => Some(Block::If),
+        "else" => Some(Block::Else),
+        "end" => Some(Block::End),
+        "for" => Some(Block::For),
+        _ => None,
+    }
+}
+
+fn tokenize(content: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = content;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find(OPEN_DELIMITER) {
+        let body_start = start + OPEN_DELIMITER.len();
+        let end = match rest[body_start..].find(CLOSE_DELIMITER) {
+            Some(end) => body_start + end,
+            None => break,
+        };
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let tag_end = end + CLOSE_DELIMITER.len();
+        tokens.push(Token::Tag {
+            body: rest[body_start..end].to_string(),
+            raw: rest[start..tag_end].to_string(),
+            offset: offset + start,
+        });
+        offset += tag_end;
+        rest = &rest[tag_end..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    strip_block_lines(&mut tokens);
+    tokens
+}
+
+// Block tags alone on their line do not leave an empty line behind
+fn strip_block_lines(tokens: &mut [Token]) {
+    // Range of each text token that is kept, computed over the original texts
+    let mut kept: Vec<(usize, usize)> = tokens
+        .iter()
+        .map(|token| match token {
+            Token::Text(text) => (0, text.len()),
+            Token::Tag { .. } => (0, 0),
+        })
+        .collect();
+
+    for idx in 0..tokens.len() {
+        let is_block = match &tokens[idx] {
+            Token::Tag { body, .. } => get_block(body).is_some(),
+            Token::Text(_) => false,
+        };
+        if !is_block {
+            continue;
+        }
+
+        let line_start = match idx.checked_sub(1) {
+            None => Some(None),
+            Some(prev) => match &tokens[prev] {
+                Token::Text(text) => {
+                    let line_start = text.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+                    // A text without newlines is only a line start at the beginning of the file
+                    let is_line_start = line_start > 0 || prev == 0;
+                    if is_line_start && text[line_start..].trim().is_empty() {
+                        Some(Some((prev, line_start)))
+                    } else {
+                        None
+                    }
+                }
+                Token::Tag { .. } => None,
+            },
+        };
+        let line_end = match tokens.get(idx + 1) {
+            None => Some(None),
+            Some(Token::Text(text)) => {
+                let line_end = text.find('\n').map(|pos| pos + 1).unwrap_or(text.len());
+                if text[..line_end].trim().is_empty() {
+                    Some(Some((idx + 1, line_end)))
+                } else {
+                    None
+                }
+            }
+            Some(Token::Tag { .. }) => None,
+        };
+
+        if let (Some(line_start), Some(line_end)) = (line_start, line_end) {
+            if let Some((prev, start)) = line_start {
+                kept[prev].1 = kept[prev].1.min(start);
+            }
+            if let Some((next, end)) = line_end {
+                kept[next].0 = kept[next].0.max(end);
+            }
+        }
+    }
+
+    for (token, (start, end)) in tokens.iter_mut().zip(kept) {
+        if let Token::Text(text) = token {
+            *text = if start < end {
+                text[start..end].to_string()
+            } else {
+                String::new()
+            };
+        }
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<Node>, TemplateError> {
+    let tokens = tokenize(content);
+    let mut iter = tokens.into_iter();
+    let (nodes, terminator) = parse_nodes(&mut iter)?;
+    match terminator {
+        None => Ok(nodes),
+        Some((block, offset)) => Err(TemplateError::new(
+            offset,
+            format!("Unexpected <[{}]> without an opening block", block),
+        )),
+    }
+}
+
+// Nodes and the else/end tag (with its offset) that finished them, if any
+type ParsedNodes = (Vec<Node>, Option<(String, usize)>);
+
+// Parses nodes until an else/end tag or the end of the content
+fn parse_nodes(iter: &mut impl Iterator<Item = Token>) -> Result<ParsedNodes, TemplateError> {
+    let mut nodes = Vec::new();
+    while let Some(token) = iter.next() {
+        let (body, raw, offset) = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag { body, raw, offset } => (body, raw, offset),
+        };
+
+        match get_block(&body) {
+            Some(Block::Else) | Some(Block::End) => {
+                return Ok((nodes, Some((body.trim().to_string(), offset))));
+            }
+            Some(Block::If) => {
+                let condition = parse_condition(&body, offset)?;
+                let (then_nodes, terminator) = parse_nodes(iter)?;
+                let else_nodes = match terminator {
+                    Some((block, _)) if block == "end" => vec![],
+                    Some((block, else_offset)) if block == "else" => match parse_nodes(iter)? {
+                        (else_nodes, Some((block, _))) if block == "end" => else_nodes,
+                        _ => {
+                            return Err(TemplateError::new(
+                                else_offset,
+                                "Missing <[end]> for <[else]>".to_string(),
+                            ))
+                        }
+                    },
+                    _ => {
+                        return Err(TemplateError::new(
+                            offset,
+                            "Missing <[end]> for <[if]>".to_string(),
+                        ))
+                    }
+                };
+                nodes.push(Node::If {
+                    condition,
+                    then_nodes,
+                    else_nodes,
+                });
+            }
+            Some(Block::For) => {
+                let (variable, group) = parse_for(&body, offset)?;
+                let body_nodes = match parse_nodes(iter)? {
+                    (body_nodes, Some((block, _))) if block == "end" => body_nodes,
+                    _ => {
+                        return Err(TemplateError::new(
+                            offset,
+                            "Missing <[end]> for <[for]>".to_string(),
+                        ))
+                    }
+                };
+                nodes.push(Node::For {
+                    variable,
+                    group,
+                    nodes: body_nodes,
+                });
+            }
+            None => nodes.push(Node::Expression(parse_expression(&body, raw, offset))),
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_condition(body: &str, offset: usize) -> Result<Condition, TemplateError> {
+    let re = CONDITION_RE.get_or_init(|| {
+        Regex::new(r#"^\s*if\s+(not\s+)?([\w.-]+)\s*(?:(==|!=)\s*(.*?))?\s*$"#).unwrap()
+    });
+    let captures = match re.captures(body) {
+        Some(captures) => captures,
+        None => {
+            return Err(TemplateError::new(
+                offset,
+                format!("Invalid condition <[{}]>", body.trim()),
+            ))
+        }
+    };
+    let negated = captures.get(1).is_some();
+    let key = captures[2].to_string();
+    let condition = match (captures.get(3), captures.get(4)) {
+        (Some(operator), Some(value)) => Condition::Equals {
+            key,
+            value: unquote(value.as_str()),
+            negated: negated != (operator.as_str() == "!="),
+        },
+        _ => Condition::Exists { key, negated },
+    };
+    Ok(condition)
+}
+
+fn parse_for(body: &str, offset: usize) -> Result<(String, String), TemplateError> {
+    let words: Vec<&str> = body.split_whitespace().collect();
+    match words.as_slice() {
+        ["for", variable, "in", group] => {
+            if !COLOR_GROUPS.contains(group) {
+                return Err(TemplateError::new(
+                    offset,
+                    format!(
+                        "Unknown group |{}| (available groups: {})",
+                        group,
+                        COLOR_GROUPS.join(", ")
+                    ),
+                ));
+            }
+            Ok((variable.to_string(), group.to_string()))
+        }
+        _ => Err(TemplateError::new(
+            offset,
+            format!(
+                "Invalid loop <[{}]> (hint: <[for color in colors]>)",
+                body.trim()
+            ),
+        )),
+    }
+}
+
+fn parse_expression(body: &str, raw: String, offset: usize) -> Expression {
+    // Legacy <[key|default]> syntax keeps its meaning
+    let legacy_re =
+        LEGACY_RE.get_or_init(|| Regex::new(r"^((?:\w|-)+)\|([\w  -]*)$").unwrap());
+    if let Some(captures) = legacy_re.captures(body) {
+        return Expression {
+            key: Some(captures[1].to_string()),
+            filters: vec![Filter {
+                name: "default".to_string(),
+                args: vec![captures[2].to_string()],
+            }],
+            raw,
+            offset,
+        };
+    }
+
+    let mut segments = split_outside_quotes(body, '|').into_iter();
+    let key = segments
+        .next()
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty());
+    let filters = segments.map(|segment| parse_filter(&segment)).collect();
+
+    Expression {
+        key,
+        filters,
+        raw,
+        offset,
+    }
+}
+
+fn parse_filter(segment: &str) -> Filter {
+    let segment = segment.trim();
+    match segment.split_once('(') {
+        Some((name, rest)) => {
+            let args = rest.trim_end().strip_suffix(')').unwrap_or(rest);
+            let args = if args.trim().is_empty() {
+                vec![]
+            } else {
+                split_outside_quotes(args, ',')
+                    .iter()
+                    .map(|arg| unquote(arg))
+                    .collect()
+            };
+            Filter {
+                name: name.trim().to_string(),
+                args,
+            }
+        }
+        None => Filter {
+            name: segment.to_string(),
+            args: vec![],
+        },
+    }
+}
+
+fn split_outside_quotes(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == separator => {
+                parts.push(current.clone());
+                current.clear();
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return text[1..text.len() - 1].to_string();
+        }
+    }
+    text.to_string()
+}
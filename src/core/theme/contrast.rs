@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use super::ANSI_KEYS;
+use crate::core::pattern::Pattern;
+use crate::utils::color::Color;
+
+// WCAG minimum ratios: AA for body text, and AA large text / UI components
+pub const TEXT_CONTRAST: f64 = 4.5;
+pub const ACCENT_CONTRAST: f64 = 3.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastCheck {
+    foreground: String,
+    background: String,
+    ratio: f64,
+    minimum: f64,
+    // Pair as seen by inverted patterns, once foreground and background are swapped
+    inverted: bool,
+}
+
+impl ContrastCheck {
+    pub fn get_foreground(&self) -> &String {
+        &self.foreground
+    }
+    pub fn get_background(&self) -> &String {
+        &self.background
+    }
+    pub fn get_ratio(&self) -> f64 {
+        self.ratio
+    }
+    pub fn get_minimum(&self) -> f64 {
+        self.minimum
+    }
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+    pub fn passes(&self) -> bool {
+        self.ratio >= self.minimum
+    }
+
+    pub fn get_grade(&self) -> &'static str {
+        match self.ratio {
+            ratio if ratio >= 7.0 => "AAA",
+            ratio if ratio >= 4.5 => "AA",
+            ratio if ratio >= 3.0 => "AA Large",
+            _ => "Fail",
+        }
+    }
+}
+
+// Text/background key pairs and their minimum ratio, as used by regular patterns
+fn get_pairs() -> Vec<(&'static str, &'static str, f64)> {
+    let mut pairs = vec![
+        ("foreground", "background", TEXT_CONTRAST),
+        (
+            "selection-foreground",
+            "selection-background",
+            TEXT_CONTRAST,
+        ),
+    ];
+    pairs.extend(
+        ANSI_KEYS
+            .iter()
+            .map(|key| (*key, "background", ACCENT_CONTRAST)),
+    );
+    pairs
+}
+
+// Contrast of every pair that matters, followed by the pairs inverted patterns end up with
+// that are not already checked (i.e, ANSI colors on the foreground). Pairs with a missing
+// or invalid color are skipped
+pub fn audit(colors: &BTreeMap<String, String>) -> Vec<ContrastCheck> {
+    let mut checks: Vec<ContrastCheck> = Vec::new();
+    for inverted in [false, true] {
+        for (foreground, background, minimum) in get_pairs() {
+            let foreground = Pattern::get_real_property(foreground, inverted);
+            let background = Pattern::get_real_property(background, inverted);
+            let already_checked = checks.iter().any(|check| {
+                let keys = (check.foreground.as_str(), check.background.as_str());
+                keys == (foreground, background) || keys == (background, foreground)
+            });
+            if already_checked {
+                continue;
+            }
+
+            let get_color = |key: &str| colors.get(key).and_then(|value| Color::from_hex(value));
+            if let (Some(fg_color), Some(bg_color)) = (get_color(foreground), get_color(background))
+            {
+                checks.push(ContrastCheck {
+                    foreground: foreground.to_string(),
+                    background: background.to_string(),
+                    ratio: fg_color.contrast(&bg_color),
+                    minimum,
+                    inverted,
+                });
+            }
+        }
+    }
+    checks
+}
+
+// New values of the colors that have to change so every regular pair reaches `target`, or
+// its own minimum if there is none. Only the text color of a pair is modified, inverted
+// pairs are left alone as fixing them would break the regular ones
+pub fn fix(colors: &BTreeMap<String, String>, target: Option<f64>) -> BTreeMap<String, String> {
+    let mut fixed = BTreeMap::new();
+    for check in audit(colors) {
+        let minimum = target.unwrap_or(check.minimum);
+        if check.inverted || check.ratio >= minimum {
+            continue;
+        }
+        let value = &colors[&check.foreground];
+        let foreground = Color::from_hex(value).unwrap();
+        let background = Color::from_hex(&colors[&check.background]).unwrap();
+        let new_value = foreground
+            .with_contrast(&background, minimum)
+            .format_like(value);
+        fixed.insert(check.foreground, new_value);
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_colors() -> BTreeMap<String, String> {
+        [
+            ("background", "2e3440"),
+            ("foreground", "d8dee9"),
+            ("selection-background", "4c566a"),
+            ("selection-foreground", "5e6a80"),
+            ("red", "bf616a"),
+            ("blue", "#3b4252"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_audit() {
+        let checks = audit(&get_colors());
+        let pairs: Vec<(&str, &str, bool)> = checks
+            .iter()
+            .map(|c| (c.foreground.as_str(), c.background.as_str(), c.inverted))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("foreground", "background", false),
+                ("selection-foreground", "selection-background", false),
+                ("red", "background", false),
+                ("blue", "background", false),
+                ("red", "foreground", true),
+                ("blue", "foreground", true),
+            ]
+        );
+        assert_eq!(checks[0].get_grade(), "AAA");
+        assert!(!checks[1].passes());
+        assert!(!checks[3].passes());
+    }
+
+    #[test]
+    fn test_fix() {
+        let colors = get_colors();
+        let fixed = fix(&colors, None);
+        assert_eq!(
+            fixed.keys().collect::<Vec<_>>(),
+            vec!["blue", "selection-foreground"]
+        );
+        assert!(fixed["blue"].starts_with('#'));
+        assert!(!fixed["selection-foreground"].starts_with('#'));
+
+        let mut colors = colors;
+        colors.extend(fixed);
+        let checks = audit(&colors);
+        assert!(checks.iter().filter(|c| !c.inverted).all(|c| c.passes()));
+        assert!(!fix(&colors, Some(7.0)).contains_key("foreground"));
+    }
+}
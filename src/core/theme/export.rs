@@ -0,0 +1,269 @@
+use log::{error, warn};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use super::{Theme, ANSI_KEYS};
+use crate::utils::color::Color;
+
+pub const EXPORT_FORMATS: [&str; 7] = [
+    "base16",
+    "xresources",
+    "alacritty",
+    "kitty",
+    "json",
+    "css-vars",
+    "gpl",
+];
+
+// Keys every format but json and css-vars needs
+const REQUIRED_KEYS: [&str; 10] = [
+    "background",
+    "foreground",
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+];
+
+// Turns a theme into a standalone color scheme for another program
+pub fn export(theme: &Theme, format: &str) -> Option<String> {
+    let colors = get_valid_colors(theme);
+
+    if !matches!(format, "json" | "css-vars") {
+        let missing: Vec<&str> = REQUIRED_KEYS
+            .into_iter()
+            .filter(|key| !colors.contains_key(*key))
+            .collect();
+        if !missing.is_empty() {
+            error!(
+                "Cannot export theme |{}| to |{}|, missing colors |{}|",
+                theme.get_name(),
+                format,
+                missing.join(", ")
+            );
+            return None;
+        }
+    }
+
+    let content = match format {
+        "base16" => export_base16(theme.get_name(), &colors),
+        "xresources" => export_xresources(theme.get_name(), &colors),
+        "alacritty" => export_alacritty(theme.get_name(), &colors),
+        "kitty" => export_kitty(theme.get_name(), &colors),
+        "json" => export_json(theme.get_name(), &colors),
+        "css-vars" => export_css_vars(theme.get_name(), &colors),
+        "gpl" => export_gpl(theme.get_name(), &colors),
+        _ => {
+            error!("Unknown export format |{}|", format);
+            return None;
+        }
+    };
+    Some(content)
+}
+
+// Theme colors that are valid hex, the rest are skipped
+fn get_valid_colors(theme: &Theme) -> BTreeMap<String, Color> {
+    let mut colors = BTreeMap::new();
+    for (key, value) in theme.get_colors() {
+        match Color::from_hex(value) {
+            Some(color) => {
+                colors.insert(key.clone(), color);
+            }
+            None => warn!("Skipping invalid color |{}| in property |{}|", value, key),
+        }
+    }
+    colors
+}
+
+// Color of an optional key, or of the key it falls back to (i.e, `red-hg` -> `red`)
+fn get_or<'a>(colors: &'a BTreeMap<String, Color>, key: &str, fallback: &str) -> &'a Color {
+    colors.get(key).unwrap_or(&colors[fallback])
+}
+
+// The 16 terminal colors, bright ones falling back to the normal ones
+fn get_ansi(colors: &BTreeMap<String, Color>) -> Vec<&Color> {
+    ANSI_KEYS
+        .iter()
+        .enumerate()
+        .map(|(idx, key)| get_or(colors, key, ANSI_KEYS[idx % 8]))
+        .collect()
+}
+
+fn bare_hex(color: &Color) -> String {
+    color.to_hex()[1..].to_string()
+}
+
+// Slots without a gtheme key (base01, base04, base06, base09 and base0F) are blended
+// from the closest ones
+fn export_base16(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let background = &colors["background"];
+    let foreground = &colors["foreground"];
+    let slots = [
+        ("base00", *background),
+        ("base01", background.mix(foreground, 0.08)),
+        ("base02", *get_or(colors, "selection-background", "black")),
+        ("base03", *get_or(colors, "black-hg", "black")),
+        ("base04", background.mix(foreground, 0.65)),
+        ("base05", *foreground),
+        (
+            "base06",
+            foreground.mix(get_or(colors, "white-hg", "white"), 0.5),
+        ),
+        ("base07", *get_or(colors, "white-hg", "white")),
+        ("base08", colors["red"]),
+        ("base09", colors["red"].mix(&colors["yellow"], 0.5)),
+        ("base0A", colors["yellow"]),
+        ("base0B", colors["green"]),
+        ("base0C", colors["cyan"]),
+        ("base0D", colors["blue"]),
+        ("base0E", colors["magenta"]),
+        ("base0F", colors["red"].mix(background, 0.4)),
+    ];
+
+    let mut content = format!("scheme: \"{}\"\nauthor: \"gtheme\"\n", name);
+    for (slot, color) in slots {
+        content += &format!("{}: \"{}\"\n", slot, bare_hex(&color));
+    }
+    content
+}
+
+fn export_xresources(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let mut content = format!("! {} (exported by gtheme)\n", name);
+    content += &format!("*.background: {}\n", colors["background"].to_hex());
+    content += &format!("*.foreground: {}\n", colors["foreground"].to_hex());
+    content += &format!(
+        "*.cursorColor: {}\n",
+        get_or(colors, "cursor", "foreground").to_hex()
+    );
+    for (idx, color) in get_ansi(colors).into_iter().enumerate() {
+        content += &format!("*.color{}: {}\n", idx, color.to_hex());
+    }
+    content
+}
+
+fn export_alacritty(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let ansi = get_ansi(colors);
+    let mut content = format!("# {} (exported by gtheme)\n", name);
+    content += &format!(
+        "[colors.primary]\nbackground = \"{}\"\nforeground = \"{}\"\n\n",
+        colors["background"].to_hex(),
+        colors["foreground"].to_hex()
+    );
+    content += &format!(
+        "[colors.cursor]\ncursor = \"{}\"\ntext = \"{}\"\n\n",
+        get_or(colors, "cursor", "foreground").to_hex(),
+        colors["background"].to_hex()
+    );
+    content += &format!(
+        "[colors.selection]\nbackground = \"{}\"\ntext = \"{}\"\n",
+        get_or(colors, "selection-background", "foreground").to_hex(),
+        get_or(colors, "selection-foreground", "background").to_hex()
+    );
+    for (group, group_colors) in [("normal", &ansi[..8]), ("bright", &ansi[8..])] {
+        content += &format!("\n[colors.{}]\n", group);
+        for (key, color) in ANSI_KEYS.iter().zip(group_colors) {
+            content += &format!("{} = \"{}\"\n", key, color.to_hex());
+        }
+    }
+    content
+}
+
+fn export_kitty(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let mut content = format!("## name: {}\n## author: gtheme\n\n", name);
+    let special = [
+        ("background", "background", "background"),
+        ("foreground", "foreground", "foreground"),
+        ("cursor", "cursor", "foreground"),
+        ("selection_background", "selection-background", "foreground"),
+        ("selection_foreground", "selection-foreground", "background"),
+    ];
+    for (kitty_key, key, fallback) in special {
+        content += &format!("{} {}\n", kitty_key, get_or(colors, key, fallback).to_hex());
+    }
+    content += "\n";
+    for (idx, color) in get_ansi(colors).into_iter().enumerate() {
+        content += &format!("color{} {}\n", idx, color.to_hex());
+    }
+    content
+}
+
+fn export_json(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let colors: BTreeMap<&String, String> = colors
+        .iter()
+        .map(|(key, color)| (key, color.to_hex()))
+        .collect();
+    let content = json!({ "name": name, "colors": colors });
+    serde_json::to_string_pretty(&content).unwrap() + "\n"
+}
+
+fn export_css_vars(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let mut content = format!("/* {} (exported by gtheme) */\n:root {{\n", name);
+    for (key, color) in colors {
+        content += &format!("  --{}: {};\n", key, color.to_hex());
+    }
+    content + "}\n"
+}
+
+// GIMP palette, also read by Inkscape and Krita
+fn export_gpl(name: &str, colors: &BTreeMap<String, Color>) -> String {
+    let mut content = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", name);
+    for (key, color) in colors {
+        let (r, g, b) = color.get_rgb();
+        content += &format!("{:>3} {:>3} {:>3}\t{}\n", r, g, b, key);
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_colors() -> BTreeMap<String, Color> {
+        REQUIRED_KEYS
+            .into_iter()
+            .enumerate()
+            .map(|(idx, key)| {
+                let value = (idx as u8) * 16;
+                (key.to_string(), Color::from_rgb(value, value, value))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_terminal_formats() {
+        let colors = get_colors();
+        let kitty = export_kitty("Test", &colors);
+        assert!(kitty.contains("## name: Test\n"));
+        assert!(kitty.contains("cursor #101010\n"));
+        assert!(kitty.contains("color9 #303030\n"));
+
+        let xresources = export_xresources("Test", &colors);
+        assert!(xresources.contains("*.color15: #909090\n"));
+
+        let alacritty = export_alacritty("Test", &colors);
+        assert!(alacritty.parse::<toml::Value>().is_ok());
+        assert!(alacritty.contains("[colors.bright]\nblack = \"#202020\"\n"));
+    }
+
+    #[test]
+    fn test_palette_formats() {
+        let colors = get_colors();
+        let base16 = export_base16("Test", &colors);
+        assert_eq!(base16.lines().count(), 18);
+        assert!(base16.contains("base00: \"000000\"\n"));
+        assert!(base16.contains("base08: \"303030\"\n"));
+
+        let gpl = export_gpl("Test", &colors);
+        assert!(gpl.contains(" 48  48  48\tred\n"));
+
+        let css = export_css_vars("Test", &colors);
+        assert!(css.contains("  --blue: #606060;\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&export_json("Test", &colors)).unwrap();
+        assert_eq!(json["colors"]["green"], "#404040");
+    }
+}
@@ -0,0 +1,525 @@
+use log::{error, info, warn};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use toml::Value;
+
+use super::{Theme, ANSI_KEYS, COLOR_KEYS};
+use crate::utils::color::Color;
+
+pub const IMPORT_FORMATS: [&str; 5] = ["base16", "xresources", "alacritty", "kitty", "iterm"];
+
+// base16 slots for each gtheme key, as base16-shell maps them. base24 schemes
+// have their own bright colors in base12-base17, used when present
+const BASE16_KEYS: [(&str, &str, Option<&str>); 21] = [
+    ("background", "base00", None),
+    ("foreground", "base05", None),
+    ("cursor", "base05", None),
+    ("selection-background", "base02", None),
+    ("selection-foreground", "base05", None),
+    ("black", "base00", None),
+    ("red", "base08", None),
+    ("green", "base0B", None),
+    ("yellow", "base0A", None),
+    ("blue", "base0D", None),
+    ("magenta", "base0E", None),
+    ("cyan", "base0C", None),
+    ("white", "base05", None),
+    ("black-hg", "base03", None),
+    ("red-hg", "base08", Some("base12")),
+    ("green-hg", "base0B", Some("base14")),
+    ("yellow-hg", "base0A", Some("base13")),
+    ("blue-hg", "base0D", Some("base16")),
+    ("magenta-hg", "base0E", Some("base17")),
+    ("cyan-hg", "base0C", Some("base15")),
+    ("white-hg", "base07", None),
+];
+
+// Creates a new theme from a color scheme of another program
+pub fn import(path: &str, name: Option<&str>, format: Option<&str>) -> bool {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Could not read |{}|: |{}|", path, e);
+            return false;
+        }
+    };
+
+    let format = match format {
+        Some(format) => format.to_string(),
+        None => match detect_format(path, &content) {
+            Some(format) => {
+                info!("Detected |{}| format for |{}|", format, path);
+                format
+            }
+            None => {
+                error!(
+                    "Could not detect the format of |{}|, try specifying it with |--format|",
+                    path
+                );
+                return false;
+            }
+        },
+    };
+
+    let parsed = match format.as_str() {
+        "base16" => parse_base16(&content),
+        "xresources" => Ok(parse_xresources(&content)),
+        "alacritty" => parse_alacritty(path, &content),
+        "kitty" => Ok(parse_kitty(&content)),
+        "iterm" => Ok(parse_iterm(&content)),
+        _ => Err(format!("Unknown format |{}|", format)),
+    };
+    let (scheme_name, colors) = match parsed {
+        Ok((scheme_name, colors)) => (scheme_name, colors),
+        Err(e) => {
+            error!("Could not import |{}|: {}", path, e);
+            return false;
+        }
+    };
+
+    let colors = match complete_colors(colors) {
+        Some(colors) => colors,
+        None => {
+            error!(
+                "Could not import |{}|, it does not define enough colors",
+                path
+            );
+            return false;
+        }
+    };
+
+    let file_stem = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let theme_name = match (name, scheme_name) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(scheme_name)) => scheme_name,
+        (None, None) => file_stem,
+    };
+    if Theme::exists(&theme_name) {
+        error!(
+            "Theme |{}| already exists, try another name with |--name|",
+            theme_name
+        );
+        return false;
+    }
+
+    let theme = Theme {
+        name: theme_name.clone(),
+        inherits: None,
+        light: None,
+        dark: None,
+        extras: BTreeMap::new(),
+        colors,
+        file: None,
+        inherited_colors: BTreeMap::new(),
+        inherited_extras: BTreeMap::new(),
+        ancestors: Vec::new(),
+    };
+    if !theme.save() {
+        return false;
+    }
+    info!(
+        "Successfully imported theme |{}| from |{}|",
+        theme_name, path
+    );
+    true
+}
+
+fn detect_format(path: &str, content: &str) -> Option<String> {
+    let file_name = Path::new(path)
+        .file_name()?
+        .to_string_lossy()
+        .to_lowercase();
+    let extension = file_name.rsplit_once('.').map(|(_, extension)| extension);
+
+    let format = match extension {
+        Some("itermcolors") => "iterm",
+        Some("conf") => "kitty",
+        Some("toml") => "alacritty",
+        Some("yaml") | Some("yml") if content.contains("base00") => "base16",
+        Some("yaml") | Some("yml") => "alacritty",
+        _ if file_name.contains("xresources") || file_name.contains("xdefaults") => "xresources",
+        _ if content.contains("<plist") => "iterm",
+        _ if Regex::new(r"(?m)^\s*\S*[*.]color\d+\s*:")
+            .unwrap()
+            .is_match(content) =>
+        {
+            "xresources"
+        }
+        _ if Regex::new(r"(?m)^\s*color\d+\s+\S")
+            .unwrap()
+            .is_match(content) =>
+        {
+            "kitty"
+        }
+        _ => return None,
+    };
+    Some(format.to_string())
+}
+
+// Bare lowercase hex, as themes store it. Accepts #rrggbb, 0xrrggbb and rrggbb
+fn normalize_color(color: &str) -> Option<String> {
+    let color = color.trim().trim_matches('"').trim_matches('\'');
+    let color = color
+        .strip_prefix("0x")
+        .or_else(|| color.strip_prefix("0X"))
+        .unwrap_or(color);
+    Color::from_hex(color).map(|color| color.to_hex()[1..].to_string())
+}
+
+// Fills the keys most schemes do not define from the ones they do, as long as the
+// background, foreground and 8 normal colors are there
+fn complete_colors(mut colors: BTreeMap<String, String>) -> Option<BTreeMap<String, String>> {
+    let required = ["background", "foreground"]
+        .into_iter()
+        .chain(ANSI_KEYS[..8].iter().copied());
+    let missing: Vec<&str> = required.filter(|key| !colors.contains_key(*key)).collect();
+    if !missing.is_empty() {
+        warn!("Missing colors |{}|", missing.join(", "));
+        return None;
+    }
+
+    let fallbacks = [
+        ("cursor", "foreground"),
+        ("selection-background", "foreground"),
+        ("selection-foreground", "background"),
+    ]
+    .into_iter()
+    .map(|(key, fallback)| (key.to_string(), fallback.to_string()))
+    .chain(
+        ANSI_KEYS[..8]
+            .iter()
+            .map(|key| (format!("{}-hg", key), key.to_string())),
+    );
+    for (key, fallback) in fallbacks {
+        if !colors.contains_key(&key) {
+            info!("Using |{}| color for |{}|", fallback, key);
+            let value = colors[&fallback].clone();
+            colors.insert(key, value);
+        }
+    }
+
+    colors.retain(|key, _| COLOR_KEYS.contains(&key.as_str()));
+    Some(colors)
+}
+
+// Scheme name, if the file has one, and colors by gtheme key
+type Parsed = (Option<String>, BTreeMap<String, String>);
+
+fn parse_base16(content: &str) -> Result<Parsed, String> {
+    let values = parse_yaml(content);
+    // base16 uses `scheme`, base24 and newer base16 schemes use `name`
+    let scheme_name = values.get("scheme").or_else(|| values.get("name")).cloned();
+    // Newer schemes nest the slots inside `palette`
+    let get_slot = |slot: &str| {
+        values
+            .get(slot)
+            .or_else(|| values.get(&format!("palette.{}", slot)))
+            .and_then(|color| normalize_color(color))
+    };
+
+    let mut colors = BTreeMap::new();
+    for (key, slot, base24_slot) in BASE16_KEYS {
+        let color = base24_slot.and_then(&get_slot).or_else(|| get_slot(slot));
+        match color {
+            Some(color) => colors.insert(key.to_string(), color),
+            None => return Err(format!("missing or invalid |{}|", slot)),
+        };
+    }
+    Ok((scheme_name, colors))
+}
+
+fn parse_xresources(content: &str) -> Parsed {
+    let mut defines: BTreeMap<String, String> = BTreeMap::new();
+    let mut colors = BTreeMap::new();
+    let re = Regex::new(r"^[\w.*-]*?[*.]?(\w+)\s*:\s*(\S+)").unwrap();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(define) = line.strip_prefix("#define") {
+            let mut parts = define.split_whitespace();
+            if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                defines.insert(name.to_string(), value.to_string());
+            }
+            continue;
+        }
+        if line.starts_with('!') || line.starts_with('#') {
+            continue;
+        }
+        let captures = match re.captures(line) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        let value = defines
+            .get(&captures[2])
+            .cloned()
+            .unwrap_or_else(|| captures[2].to_string());
+        let key = match &captures[1] {
+            "background" => "background".to_string(),
+            "foreground" => "foreground".to_string(),
+            "cursorColor" => "cursor".to_string(),
+            "highlightColor" => "selection-background".to_string(),
+            "highlightTextColor" => "selection-foreground".to_string(),
+            other => match get_ansi_key(other.strip_prefix("color")) {
+                Some(key) => key,
+                None => continue,
+            },
+        };
+        if let Some(color) = normalize_color(&value) {
+            colors.insert(key, color);
+        }
+    }
+    (None, colors)
+}
+
+fn parse_kitty(content: &str) -> Parsed {
+    let mut colors = BTreeMap::new();
+    let mut scheme_name = None;
+    for line in content.lines() {
+        let line = line.trim();
+        // Themes from kitty-themes start with `## name: Theme Name`
+        if let Some(name) = line.strip_prefix("## name:") {
+            scheme_name = Some(name.trim().to_string());
+            continue;
+        }
+        let (key, value) = match line.split_once(char::is_whitespace) {
+            Some((key, value)) if !key.starts_with('#') => (key, value),
+            _ => continue,
+        };
+        let key = match key {
+            "background" => "background".to_string(),
+            "foreground" => "foreground".to_string(),
+            "cursor" => "cursor".to_string(),
+            "selection_background" => "selection-background".to_string(),
+            "selection_foreground" => "selection-foreground".to_string(),
+            other => match get_ansi_key(other.strip_prefix("color")) {
+                Some(key) => key,
+                None => continue,
+            },
+        };
+        if let Some(color) = normalize_color(value) {
+            colors.insert(key, color);
+        }
+    }
+    (scheme_name, colors)
+}
+
+fn parse_alacritty(path: &str, content: &str) -> Result<Parsed, String> {
+    let values = if path.ends_with(".toml") {
+        let value = content
+            .parse::<Value>()
+            .map_err(|e| format!("invalid TOML: |{}|", e))?;
+        let mut values = BTreeMap::new();
+        flatten_toml("", &value, &mut values);
+        values
+    } else {
+        parse_yaml(content)
+    };
+
+    let mut colors = BTreeMap::new();
+    let mut insert = |key: &str, path: &str| {
+        if let Some(color) = values
+            .get(&format!("colors.{}", path))
+            .and_then(|color| normalize_color(color))
+        {
+            colors.insert(key.to_string(), color);
+        }
+    };
+    insert("background", "primary.background");
+    insert("foreground", "primary.foreground");
+    insert("cursor", "cursor.cursor");
+    insert("selection-background", "selection.background");
+    insert("selection-foreground", "selection.text");
+    for (idx, key) in ANSI_KEYS.iter().enumerate() {
+        let group = if idx < 8 { "normal" } else { "bright" };
+        let name = ANSI_KEYS[idx % 8];
+        insert(key, &format!("{}.{}", group, name));
+    }
+    Ok((None, colors))
+}
+
+fn parse_iterm(content: &str) -> Parsed {
+    let entry_re = Regex::new(r"(?s)<key>([^<]+)</key>\s*<dict>(.*?)</dict>").unwrap();
+    let component_re =
+        Regex::new(r"(?s)<key>(Red|Green|Blue) Component</key>\s*<real>([^<]+)</real>").unwrap();
+
+    let mut colors = BTreeMap::new();
+    for entry in entry_re.captures_iter(content) {
+        let key = match &entry[1] {
+            "Background Color" => "background".to_string(),
+            "Foreground Color" => "foreground".to_string(),
+            "Cursor Color" => "cursor".to_string(),
+            "Selection Color" => "selection-background".to_string(),
+            "Selected Text Color" => "selection-foreground".to_string(),
+            other => {
+                let index = other
+                    .strip_prefix("Ansi ")
+                    .and_then(|other| other.strip_suffix(" Color"));
+                match get_ansi_key(index) {
+                    Some(key) => key,
+                    None => continue,
+                }
+            }
+        };
+
+        let mut rgb = [0u8; 3];
+        for component in component_re.captures_iter(&entry[2]) {
+            let channel = match &component[1] {
+                "Red" => 0,
+                "Green" => 1,
+                _ => 2,
+            };
+            let value = component[2].trim().parse::<f64>().unwrap_or(0.0);
+            rgb[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        let [r, g, b] = rgb;
+        colors.insert(key, Color::from_rgb(r, g, b).to_hex()[1..].to_string());
+    }
+    (None, colors)
+}
+
+fn get_ansi_key(index: Option<&str>) -> Option<String> {
+    let index = index?.trim().parse::<usize>().ok()?;
+    ANSI_KEYS.get(index).map(|key| key.to_string())
+}
+
+// Indentation based YAML subset: nested mappings of scalars, flattened into dotted paths
+fn parse_yaml(content: &str) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    let mut parents: Vec<(usize, String)> = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let (key, value) = match trimmed.split_once(':') {
+            Some((key, value)) => (key.trim().trim_matches('"').trim_matches('\''), value),
+            None => continue,
+        };
+
+        while matches!(parents.last(), Some((parent_indent, _)) if *parent_indent >= indent) {
+            parents.pop();
+        }
+        let path = parents
+            .iter()
+            .map(|(_, parent)| parent.as_str())
+            .chain([key])
+            .collect::<Vec<_>>()
+            .join(".");
+
+        let value = get_yaml_scalar(value);
+        if value.is_empty() {
+            parents.push((indent, key.to_string()));
+        } else {
+            values.insert(path, value);
+        }
+    }
+    values
+}
+
+fn get_yaml_scalar(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(rest) = value.strip_prefix(quote) {
+            return rest.split(quote).next().unwrap_or("").to_string();
+        }
+    }
+    // Unquoted values end where a comment starts
+    match value.find(" #") {
+        Some(end) => value[..end].trim().to_string(),
+        None if value.starts_with('#') => String::new(),
+        None => value.to_string(),
+    }
+}
+
+fn flatten_toml(prefix: &str, value: &Value, values: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_toml(&path, value, values);
+            }
+        }
+        Value::String(value) => {
+            values.insert(prefix.to_string(), value.clone());
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base16() {
+        let mut content = "scheme: \"Test\"\nauthor: \"me\"\n".to_string();
+        for idx in 0..16 {
+            content += &format!(
+                "base{:02X}: \"{:02x}{:02x}{:02x}\" # comment\n",
+                idx, idx, idx, idx
+            );
+        }
+        let (name, colors) = parse_base16(&content).unwrap();
+        assert_eq!(name, Some("Test".to_string()));
+        assert_eq!(colors["background"], "000000");
+        assert_eq!(colors["red"], "080808");
+        assert_eq!(colors["white-hg"], "070707");
+        assert_eq!(colors.len(), 21);
+    }
+
+    #[test]
+    fn test_terminal_schemes() {
+        let content = "! comment\n#define bg #2e3440\n*.background: bg\n*foreground: #d8dee9\nURxvt*color9: #BF616A\n";
+        let (_, colors) = parse_xresources(content);
+        assert_eq!(colors["background"], "2e3440");
+        assert_eq!(colors["foreground"], "d8dee9");
+        assert_eq!(colors["red-hg"], "bf616a");
+
+        let content =
+            "## name: Kitty Test\nbackground #2e3440\ncolor1   #bf616a\n# color2 #000000\n";
+        let (name, colors) = parse_kitty(content);
+        assert_eq!(name, Some("Kitty Test".to_string()));
+        assert_eq!(colors.len(), 2);
+        assert_eq!(colors["red"], "bf616a");
+
+        let content =
+            "colors:\n  primary:\n    background: '0x2e3440'\n  bright:\n    red: '#bf616a'\n";
+        let (_, colors) = parse_alacritty("a.yml", content).unwrap();
+        assert_eq!(colors["background"], "2e3440");
+        assert_eq!(colors["red-hg"], "bf616a");
+
+        let content = "[colors.normal]\nblue = \"#81a1c1\"\n";
+        let (_, colors) = parse_alacritty("a.toml", content).unwrap();
+        assert_eq!(colors["blue"], "81a1c1");
+
+        let content = "<dict><key>Ansi 4 Color</key>\n<dict>\n<key>Blue Component</key>\n<real>1</real>\n<key>Red Component</key>\n<real>0.5</real>\n</dict></dict>";
+        let (_, colors) = parse_iterm(content);
+        assert_eq!(colors["blue"], "8000ff");
+    }
+
+    #[test]
+    fn test_complete_colors() {
+        let mut colors: BTreeMap<String, String> = ["background", "foreground"]
+            .into_iter()
+            .chain(ANSI_KEYS[..8].iter().copied())
+            .map(|key| (key.to_string(), "2e3440".to_string()))
+            .collect();
+        colors.insert("unknown".to_string(), "ffffff".to_string());
+        let completed = complete_colors(colors.clone()).unwrap();
+        assert_eq!(completed.len(), COLOR_KEYS.len());
+
+        colors.remove("red");
+        assert!(complete_colors(colors).is_none());
+    }
+}
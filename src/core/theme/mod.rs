@@ -5,12 +5,124 @@ use std::fs::{self, File, OpenOptions};
 use std::io::prelude::*;
 
 use crate::core;
+use crate::core::desktop::Desktop;
+use crate::core::diagnostic::Diagnostic;
+use crate::core::palette;
+use crate::utils::color::Color;
+
+mod contrast;
+mod export;
+mod import;
+mod validation;
+
+pub use contrast::ContrastCheck;
+pub use export::EXPORT_FORMATS;
+pub use import::IMPORT_FORMATS;
+
+// Colors every theme must define
+pub const COLOR_KEYS: [&str; 21] = [
+    "background",
+    "foreground",
+    "cursor",
+    "selection-background",
+    "selection-foreground",
+    "black",
+    "black-hg",
+    "red",
+    "red-hg",
+    "green",
+    "green-hg",
+    "yellow",
+    "yellow-hg",
+    "blue",
+    "blue-hg",
+    "magenta",
+    "magenta-hg",
+    "cyan",
+    "cyan-hg",
+    "white",
+    "white-hg",
+];
+
+// gtheme keys of the 16 terminal colors, in ANSI order
+const ANSI_KEYS: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "black-hg",
+    "red-hg",
+    "green-hg",
+    "yellow-hg",
+    "blue-hg",
+    "magenta-hg",
+    "cyan-hg",
+    "white-hg",
+];
+
+// Whether a theme has a light or a dark background
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Dark,
+    Light,
+}
+
+impl Mode {
+    pub fn from(mode: &str) -> Option<Mode> {
+        match mode.to_lowercase().as_str() {
+            "dark" => Some(Mode::Dark),
+            "light" => Some(Mode::Light),
+            _ => None,
+        }
+    }
+    pub fn opposite(&self) -> Mode {
+        match self {
+            Mode::Dark => Mode::Light,
+            Mode::Light => Mode::Dark,
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Mode::Dark => write!(f, "dark"),
+            Mode::Light => write!(f, "light"),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Theme {
     name: String,
+    // Parent theme, whose colors and extras are used unless this theme overrides them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inherits: Option<String>,
+    // Light and dark counterparts of this theme, used by `theme toggle-mode`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    light: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dark: Option<String>,
+    // Legacy extras arguments, extras now keep them in their own storage
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     extras: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
     colors: BTreeMap<String, String>,
+    // File the theme was read from, if any
+    #[serde(skip)]
+    file: Option<ThemeFile>,
+    // Ancestor that defines each inherited color and extra
+    #[serde(skip)]
+    inherited_colors: BTreeMap<String, String>,
+    #[serde(skip)]
+    inherited_extras: BTreeMap<String, String>,
+    // Names of the parent, grandparent... of the theme
+    #[serde(skip)]
+    ancestors: Vec<String>,
 }
 
 impl Theme {
@@ -23,8 +135,155 @@ impl Theme {
     pub fn get_colors(&self) -> &BTreeMap<String, String> {
         &self.colors
     }
+    pub fn get_inherits(&self) -> &Option<String> {
+        &self.inherits
+    }
+    pub fn get_light(&self) -> &Option<String> {
+        &self.light
+    }
+    pub fn get_dark(&self) -> &Option<String> {
+        &self.dark
+    }
+    // Theme where the color comes from, which is this theme unless it was inherited
+    pub fn get_color_origin(&self, key: &str) -> &String {
+        self.inherited_colors.get(key).unwrap_or(&self.name)
+    }
+    pub fn get_extra_origin(&self, key: &str) -> &String {
+        self.inherited_extras.get(key).unwrap_or(&self.name)
+    }
+    pub fn get_ancestors(&self) -> &Vec<String> {
+        &self.ancestors
+    }
 
     pub fn from(theme: &ThemeFile) -> Self {
+        let mut result = Self::read(theme);
+        result.file = Some(theme.clone());
+        result.resolve_inheritance(&mut vec![theme.get_name().clone()]);
+        result
+    }
+
+    // Merges colors and extras from the ancestors, `chain` holds the themes already visited
+    fn resolve_inheritance(&mut self, chain: &mut Vec<String>) {
+        let parent_name = match &self.inherits {
+            Some(parent_name) => parent_name.clone(),
+            None => return,
+        };
+        let parent_file = match Theme::get_by_name(&parent_name) {
+            Some(parent_file) => parent_file,
+            None => {
+                error!(
+                    "Theme |{}| inherits from |{}|, which does not exist",
+                    self.get_name(),
+                    parent_name
+                );
+                return;
+            }
+        };
+        if chain
+            .iter()
+            .any(|name| name.to_lowercase() == parent_file.get_name().to_lowercase())
+        {
+            error!(
+                "Inheritance cycle detected: |{} -> {}|, ignoring parent theme",
+                chain.join(" -> "),
+                parent_file.get_name()
+            );
+            return;
+        }
+
+        chain.push(parent_file.get_name().clone());
+        let mut parent = Self::read(&parent_file);
+        parent.resolve_inheritance(chain);
+        self.ancestors = vec![parent.get_name().clone()];
+        self.ancestors.extend(parent.ancestors.iter().cloned());
+
+        for (key, value) in &parent.colors {
+            if !self.colors.contains_key(key) {
+                self.colors.insert(key.clone(), value.clone());
+                let origin = parent.get_color_origin(key).clone();
+                self.inherited_colors.insert(key.clone(), origin);
+            }
+        }
+        for (key, args) in &parent.extras {
+            if !self.extras.contains_key(key) {
+                self.extras.insert(key.clone(), args.clone());
+                let origin = parent.get_extra_origin(key).clone();
+                self.inherited_extras.insert(key.clone(), origin);
+            }
+        }
+    }
+
+    // A theme declaring only a light counterpart is dark and vice versa, as well as a theme
+    // declared as the counterpart of another one. Otherwise the background color decides.
+    // `declaring` is the result of `get_declaring_theme`, so themes are only scanned once
+    pub fn get_mode(&self, declaring: &Option<(Mode, ThemeFile)>) -> Mode {
+        match (&self.light, &self.dark) {
+            (Some(_), None) => Mode::Dark,
+            (None, Some(_)) => Mode::Light,
+            (light, dark) => match declaring {
+                Some((mode, _)) if light.is_none() && dark.is_none() => *mode,
+                _ => match self
+                    .colors
+                    .get("background")
+                    .and_then(|bg| Color::from_hex(bg))
+                {
+                    Some(background) if background.luminance() > 0.5 => Mode::Light,
+                    _ => Mode::Dark,
+                },
+            },
+        }
+    }
+
+    // Theme declared as the `mode` counterpart, or else the theme declaring this one as its
+    // opposite counterpart, so pairs only need to be declared on one side
+    pub fn get_counterpart(
+        &self,
+        mode: Mode,
+        declaring: &Option<(Mode, ThemeFile)>,
+    ) -> Option<ThemeFile> {
+        let declared = match mode {
+            Mode::Light => &self.light,
+            Mode::Dark => &self.dark,
+        };
+        if let Some(counterpart) = declared {
+            return Self::get_by_name(counterpart);
+        }
+        match declaring {
+            Some((declared_mode, theme_file)) if *declared_mode == mode.opposite() => {
+                Some(theme_file.clone())
+            }
+            _ => None,
+        }
+    }
+
+    // Theme declaring this one as its counterpart, and the mode it declares it with. Every
+    // theme file is read, so callers look it up once and pass it along
+    pub fn get_declaring_theme(&self) -> Option<(Mode, ThemeFile)> {
+        let is_this_theme = |name: &Option<String>| {
+            name.as_ref()
+                .is_some_and(|name| name.to_lowercase() == self.name.to_lowercase())
+        };
+        Self::get_themes().into_iter().find_map(|theme_file| {
+            let theme = Self::read(&theme_file);
+            if is_this_theme(&theme.light) {
+                Some((Mode::Light, theme_file))
+            } else if is_this_theme(&theme.dark) {
+                Some((Mode::Dark, theme_file))
+            } else {
+                None
+            }
+        })
+    }
+
+    // Validates the file the theme was read from
+    pub fn check(&self, desktop: Option<&Desktop>) -> Vec<Diagnostic> {
+        match &self.file {
+            Some(theme_file) => theme_file.check(desktop),
+            None => vec![],
+        }
+    }
+
+    fn read(theme: &ThemeFile) -> Self {
         let mut file = match File::open(theme.get_path()) {
             Ok(file) => file,
             Err(e) => {
@@ -98,8 +357,15 @@ impl Theme {
         let extras = BTreeMap::new();
         Theme {
             name: name.to_string(),
+            inherits: None,
+            light: None,
+            dark: None,
             colors,
             extras,
+            file: None,
+            inherited_colors: BTreeMap::new(),
+            inherited_extras: BTreeMap::new(),
+            ancestors: Vec::new(),
         }
     }
 
@@ -190,13 +456,17 @@ impl Theme {
         vec
     }
 
-    fn save(&self) {
+    // Writes the theme into the file it was read from, or into a new one named after it
+    fn save(&self) -> bool {
         let content = toml::to_string_pretty(self).unwrap();
-        let path = format!(
-            "{}/themes/{}.toml",
-            core::expand_path(core::GTHEME_HOME),
-            self.get_name()
-        );
+        let path = match &self.file {
+            Some(theme_file) => theme_file.get_path().clone(),
+            None => format!(
+                "{}/themes/{}.toml",
+                core::expand_path(core::GTHEME_HOME),
+                self.get_name()
+            ),
+        };
 
         let mut file = match OpenOptions::new()
             .create(true)
@@ -207,12 +477,106 @@ impl Theme {
             Ok(f) => f,
             Err(e) => {
                 error!("Could not open |{}|: |{}|", &path, e);
-                return;
+                return false;
             }
         };
         if let Err(e) = file.write_all(&content.as_bytes()) {
             error!("Could not save theme in |{}|: |{}|", &path, e);
+            return false;
+        }
+        true
+    }
+
+    // Creates a theme from a color scheme file of another program, detecting its format
+    // unless one of IMPORT_FORMATS is given
+    pub fn import(path: &str, name: Option<&str>, format: Option<&str>) -> bool {
+        import::import(path, name, format)
+    }
+
+    // Theme colors in one of EXPORT_FORMATS, for programs without a pattern
+    pub fn export(&self, format: &str) -> Option<String> {
+        export::export(self, format)
+    }
+
+    // Creates a theme with every standard color from the dominant colors of an image,
+    // named after the image unless a name is given
+    pub fn generate(image_path: &str, name: Option<&str>, mode: Mode) -> bool {
+        let theme_name = match name {
+            Some(name) => name.to_string(),
+            None => std::path::Path::new(image_path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+        if Self::exists(&theme_name) {
+            error!(
+                "Theme |{}| already exists, try another name with |--name|",
+                theme_name
+            );
+            return false;
+        }
+
+        let clusters = match palette::extract(image_path) {
+            Some(clusters) => clusters,
+            None => return false,
+        };
+        let theme = Theme {
+            name: theme_name.clone(),
+            inherits: None,
+            light: None,
+            dark: None,
+            extras: BTreeMap::new(),
+            colors: palette::to_theme_colors(&clusters, mode),
+            file: None,
+            inherited_colors: BTreeMap::new(),
+            inherited_extras: BTreeMap::new(),
+            ancestors: Vec::new(),
+        };
+        if !theme.save() {
+            return false;
         }
+        info!(
+            "Successfully generated theme |{}| from |{}|",
+            theme_name, image_path
+        );
+        true
+    }
+
+    // WCAG contrast of the color pairs patterns rely on, inverted patterns included
+    pub fn get_contrast(&self) -> Vec<ContrastCheck> {
+        contrast::audit(&self.colors)
+    }
+
+    // Nudges the lightness of low contrast colors until they reach `target` (or the minimum
+    // of their pair) and saves them in the theme file. Returns the fixed colors, or None if
+    // they could not be saved
+    pub fn fix_contrast(
+        &self,
+        target: Option<f64>,
+        dry_run: bool,
+    ) -> Option<BTreeMap<String, String>> {
+        let fixed = contrast::fix(&self.colors, target);
+        if dry_run || fixed.is_empty() {
+            return Some(fixed);
+        }
+        // Inherited colors are overridden in this theme, the parent stays untouched
+        let mut theme = match &self.file {
+            Some(theme_file) => Self::read(theme_file),
+            None => {
+                error!("Theme |{}| has no file to save into", self.get_name());
+                return None;
+            }
+        };
+        theme.colors.extend(fixed.clone());
+        if !theme.save() {
+            return None;
+        }
+        info!(
+            "Fixed |{}| colors in theme |{}|",
+            fixed.len(),
+            self.get_name()
+        );
+        Some(fixed)
     }
 
     pub fn new_skeleton(theme_name: &str) {
@@ -228,46 +592,27 @@ impl Theme {
             return;
         }
 
-        let mut colors = BTreeMap::new();
-
-        let pairs = vec![
-            ("background", ""),
-            ("foreground", ""),
-            ("cursor", ""),
-            ("selection-background", ""),
-            ("selection-foreground", ""),
-            ("black", ""),
-            ("black-hg", ""),
-            ("red", ""),
-            ("red-hg", ""),
-            ("green", ""),
-            ("green-hg", ""),
-            ("yellow", ""),
-            ("yellow-hg", ""),
-            ("blue", ""),
-            ("blue-hg", ""),
-            ("magenta", ""),
-            ("magenta-hg", ""),
-            ("cyan", ""),
-            ("cyan-hg", ""),
-            ("white", ""),
-            ("white-hg", ""),
-        ];
-
-        colors.extend(
-            pairs
-                .into_iter()
-                .map(|(key, value)| (key.to_string(), value.to_string())),
-        );
+        let colors = COLOR_KEYS
+            .iter()
+            .map(|key| (key.to_string(), String::new()))
+            .collect();
 
         let extras = BTreeMap::new();
         let theme = Theme {
             name: theme_name.to_string(),
+            inherits: None,
+            light: None,
+            dark: None,
             colors,
             extras,
+            file: None,
+            inherited_colors: BTreeMap::new(),
+            inherited_extras: BTreeMap::new(),
+            ancestors: Vec::new(),
         };
-        theme.save();
-        info!("Successfully created theme |{}|", theme_name);
+        if theme.save() {
+            info!("Successfully created theme |{}|", theme_name);
+        }
     }
 }
 
@@ -287,6 +632,53 @@ impl ThemeFile {
     pub fn get_path(&self) -> &String {
         &self.path
     }
+
+    pub fn check(&self, desktop: Option<&Desktop>) -> Vec<Diagnostic> {
+        let content = match fs::read_to_string(self.get_path()) {
+            Ok(content) => content,
+            Err(e) => {
+                return vec![Diagnostic::error(
+                    self.get_path(),
+                    (1, 1),
+                    format!("Could not read theme file: |{}|", e),
+                )]
+            }
+        };
+        validation::check(self, &content, &Theme::get_themes(), desktop)
+    }
+
+    // Removes extras from the theme file itself, inherited ones stay in the parent. Only the
+    // lines of those extras change, so comments and the layout of the file are kept
+    pub fn remove_extras(&self, names: &[String]) -> bool {
+        let path = self.get_path();
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Could not read theme file |{}|: |{}|", path, e);
+                return false;
+            }
+        };
+        let edited = remove_extras_entries(&content, names);
+        // The extras could be written in a way the edit does not handle, like inline tables
+        let removed = match toml::from_str::<Theme>(&edited) {
+            Ok(theme) => names.iter().all(|name| !theme.extras.contains_key(name)),
+            Err(_) => false,
+        };
+        if !removed {
+            error!(
+                "Could not remove extras |{}| from theme file |{}|, remove them by hand",
+                names.join(", "),
+                path
+            );
+            return false;
+        }
+        if let Err(e) = fs::write(path, edited) {
+            error!("Could not write theme file |{}|: |{}|", path, e);
+            return false;
+        }
+        true
+    }
+
     pub fn remove(&self) {
         let path = self.get_path();
         let theme_name = self.get_name();
@@ -300,3 +692,207 @@ impl ThemeFile {
         info!("Successfully removed theme |{theme_name}|");
     }
 }
+
+// Content of a theme file without the given entries of its `[extras]` table, which is
+// dropped too if nothing else is left in it
+fn remove_extras_entries(content: &str, names: &[String]) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_extras = false;
+    let mut header = None;
+    let mut kept_entries = 0;
+    // Open brackets of the value being read, multi-line arrays span several lines
+    let mut depth = 0;
+    let mut skipping = false;
+    for line in content.lines() {
+        if depth > 0 {
+            depth += get_bracket_balance(line);
+            if !skipping {
+                lines.push(line);
+            }
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.starts_with("[[") {
+            let name = trimmed[1..]
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .trim_matches('"');
+            in_extras = name == "extras";
+            if in_extras {
+                header = Some(lines.len());
+                kept_entries = 0;
+            }
+            lines.push(line);
+            continue;
+        }
+        if let (true, Some((key, value))) = (in_extras, trimmed.split_once('=')) {
+            let key = key.trim().trim_matches('"').trim_matches('\'');
+            depth = get_bracket_balance(value);
+            skipping = names.iter().any(|name| name == key);
+            if skipping {
+                continue;
+            }
+            kept_entries += 1;
+        }
+        lines.push(line);
+    }
+    if let (Some(header), 0) = (header, kept_entries) {
+        lines.remove(header);
+    }
+
+    let mut edited = lines.join("\n");
+    if content.ends_with('\n') {
+        edited.push('\n');
+    }
+    edited
+}
+
+fn get_bracket_balance(line: &str) -> i32 {
+    let mut balance = 0;
+    let mut in_string = false;
+    for c in line.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => balance += 1,
+            ']' if !in_string => balance -= 1,
+            '#' if !in_string => break,
+            _ => (),
+        }
+    }
+    balance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_extras_entries() {
+        let content = "name = \"Nord\"\n\n[extras]\n# Wallpaper of the theme\nwallpaper = [\n    \"nord.png\",\n    \"fill\",\n]\nbars = [\"top\"]\n\n[colors]\nbackground = \"#2e3440\"\n";
+        let names = vec!["wallpaper".to_string()];
+        assert_eq!(
+            remove_extras_entries(content, &names),
+            "name = \"Nord\"\n\n[extras]\n# Wallpaper of the theme\nbars = [\"top\"]\n\n[colors]\nbackground = \"#2e3440\"\n"
+        );
+        let names = vec!["wallpaper".to_string(), "bars".to_string()];
+        assert_eq!(
+            remove_extras_entries(content, &names),
+            "name = \"Nord\"\n\n# Wallpaper of the theme\n\n[colors]\nbackground = \"#2e3440\"\n"
+        );
+    }
+
+    #[test]
+    fn test_save() {
+        core::testing::with_home("theme-save", |home| {
+            let themes = home.join(".config/gtheme/themes");
+            let content = "name = \"Custom Nord\"\n[colors]\nbackground = \"000000\"\n";
+            fs::write(themes.join("custom.toml"), content).unwrap();
+            let mut theme = Theme::get_by_name("custom").unwrap().to_theme();
+            theme
+                .colors
+                .insert("foreground".to_string(), "ffffff".to_string());
+
+            assert!(theme.save());
+            assert!(!themes.join("Custom Nord.toml").exists());
+            let saved = fs::read_to_string(themes.join("custom.toml")).unwrap();
+            assert!(saved.contains("foreground"));
+
+            fs::remove_dir_all(&themes).unwrap();
+            assert!(!theme.save());
+        });
+    }
+
+    #[test]
+    fn test_resolve_inheritance() {
+        core::testing::with_home("theme-inherits", |home| {
+            let themes = home.join(".config/gtheme/themes");
+            let grandparent = "name = \"Base\"\n[extras]\nwallpaper = [\"base.png\"]\nbar = [\"top\"]\n[colors]\nbackground = \"000000\"\nforeground = \"ffffff\"\nred = \"ff0000\"\n";
+            fs::write(themes.join("Base.toml"), grandparent).unwrap();
+            let parent = "name = \"Dim\"\ninherits = \"Base\"\n[extras]\nbar = [\"bottom\"]\n[colors]\nforeground = \"cccccc\"\n";
+            fs::write(themes.join("Dim.toml"), parent).unwrap();
+            let child = "name = \"Night\"\ninherits = \"Dim\"\n[colors]\nbackground = \"111111\"\n";
+            fs::write(themes.join("Night.toml"), child).unwrap();
+
+            let theme = Theme::get_by_name("Night").unwrap().to_theme();
+            assert_eq!(theme.get_ancestors(), &vec!["Dim", "Base"]);
+            // The closest theme defining a color or extra wins
+            assert_eq!(theme.get_colors()["background"], "111111");
+            assert_eq!(theme.get_colors()["foreground"], "cccccc");
+            assert_eq!(theme.get_colors()["red"], "ff0000");
+            assert_eq!(theme.get_color_origin("background"), "Night");
+            assert_eq!(theme.get_color_origin("foreground"), "Dim");
+            assert_eq!(theme.get_color_origin("red"), "Base");
+            assert_eq!(theme.get_extras()["bar"], vec!["bottom"]);
+            assert_eq!(theme.get_extras()["wallpaper"], vec!["base.png"]);
+            assert_eq!(theme.get_extra_origin("bar"), "Dim");
+            assert_eq!(theme.get_extra_origin("wallpaper"), "Base");
+        });
+    }
+
+    #[test]
+    fn test_resolve_inheritance_errors() {
+        core::testing::with_home("theme-inherits-errors", |home| {
+            let themes = home.join(".config/gtheme/themes");
+            let first = "name = \"A\"\ninherits = \"B\"\n[colors]\nbackground = \"000000\"\n";
+            fs::write(themes.join("A.toml"), first).unwrap();
+            let second = "name = \"B\"\ninherits = \"A\"\n[colors]\nforeground = \"ffffff\"\n";
+            fs::write(themes.join("B.toml"), second).unwrap();
+            let orphan =
+                "name = \"Orphan\"\ninherits = \"Missing\"\n[colors]\nbackground = \"222222\"\n";
+            fs::write(themes.join("Orphan.toml"), orphan).unwrap();
+
+            // The cycle is cut where it closes, so each theme still gets the other colors
+            let theme = Theme::get_by_name("A").unwrap().to_theme();
+            assert_eq!(theme.get_ancestors(), &vec!["B"]);
+            assert_eq!(theme.get_colors()["foreground"], "ffffff");
+            assert_eq!(theme.get_color_origin("foreground"), "B");
+            let theme = Theme::get_by_name("B").unwrap().to_theme();
+            assert_eq!(theme.get_ancestors(), &vec!["A"]);
+            assert_eq!(theme.get_colors()["background"], "000000");
+
+            let theme = Theme::get_by_name("Orphan").unwrap().to_theme();
+            assert!(theme.get_ancestors().is_empty());
+            assert_eq!(theme.get_colors().len(), 1);
+            assert_eq!(theme.get_color_origin("background"), "Orphan");
+        });
+    }
+
+    #[test]
+    fn test_mode_counterparts() {
+        core::testing::with_home("theme-modes", |home| {
+            let themes = home.join(".config/gtheme/themes");
+            // Declared as dark with a light background, the declaration wins
+            let dark = "name = \"Dusk\"\nlight = \"Dawn\"\n[colors]\nbackground = \"ffffff\"\n";
+            fs::write(themes.join("Dusk.toml"), dark).unwrap();
+            let light = "name = \"Dawn\"\n[colors]\nbackground = \"000000\"\n";
+            fs::write(themes.join("Dawn.toml"), light).unwrap();
+            let single = "name = \"Solo\"\n[colors]\nbackground = \"000000\"\n";
+            fs::write(themes.join("Solo.toml"), single).unwrap();
+
+            let dusk = Theme::get_by_name("Dusk").unwrap().to_theme();
+            let declaring = dusk.get_declaring_theme();
+            assert!(declaring.is_none());
+            assert_eq!(dusk.get_mode(&declaring), Mode::Dark);
+            let counterpart = dusk.get_counterpart(Mode::Light, &declaring).unwrap();
+            assert_eq!(counterpart.get_name(), "Dawn");
+            assert!(dusk.get_counterpart(Mode::Dark, &declaring).is_none());
+
+            // Dawn declares nothing, its mode and counterpart come from Dusk
+            let dawn = Theme::get_by_name("Dawn").unwrap().to_theme();
+            let declaring = dawn.get_declaring_theme();
+            assert_eq!(declaring.as_ref().unwrap().0, Mode::Light);
+            assert_eq!(dawn.get_mode(&declaring), Mode::Light);
+            let counterpart = dawn.get_counterpart(Mode::Dark, &declaring).unwrap();
+            assert_eq!(counterpart.get_name(), "Dusk");
+            assert!(dawn.get_counterpart(Mode::Light, &declaring).is_none());
+
+            let solo = Theme::get_by_name("Solo").unwrap().to_theme();
+            let declaring = solo.get_declaring_theme();
+            assert!(declaring.is_none());
+            assert_eq!(solo.get_mode(&declaring), Mode::Dark);
+            assert!(solo.get_counterpart(Mode::Light, &declaring).is_none());
+        });
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use toml::Value;
+
+use super::{Theme, ThemeFile, COLOR_KEYS};
+use crate::core::desktop::Desktop;
+use crate::core::diagnostic::Diagnostic;
+
+const THEME_FIELDS: [&str; 6] = ["name", "inherits", "light", "dark", "colors", "extras"];
+
+// Line and column of every key and table header, by their dotted path (i.e, `colors.red`)
+fn get_positions(content: &str) -> BTreeMap<String, (usize, usize)> {
+    let mut positions = BTreeMap::new();
+    let mut section = String::new();
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let col = line.len() - trimmed.len() + 1;
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = trimmed.strip_prefix('[') {
+            if let Some((header, _)) = header.split_once(']') {
+                section = unquote_path(header);
+                positions.insert(section.clone(), (idx + 1, col));
+            }
+            continue;
+        }
+        if let Some((key, _)) = trimmed.split_once('=') {
+            let key = unquote_path(key);
+            let path = if section.is_empty() {
+                key
+            } else {
+                format!("{}.{}", section, key)
+            };
+            positions.entry(path).or_insert((idx + 1, col));
+        }
+    }
+    positions
+}
+
+fn unquote_path(path: &str) -> String {
+    path.split('.')
+        .map(|part| part.trim().trim_matches('"').trim_matches('\''))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+pub fn is_valid_hex(color: &str) -> bool {
+    color.len() == 6 && color.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Checks the theme file against the expected schema. Extras are only checked when a desktop
+// is given, since they depend on it
+pub fn check(
+    theme_file: &ThemeFile,
+    content: &str,
+    all_themes: &[ThemeFile],
+    desktop: Option<&Desktop>,
+) -> Vec<Diagnostic> {
+    let path = theme_file.get_path();
+    let mut diagnostics = Vec::new();
+
+    let table = match content.parse::<Value>() {
+        Ok(Value::Table(table)) => table,
+        Ok(_) => unreachable!("A TOML document is always a table"),
+        Err(e) => {
+            let position = e
+                .line_col()
+                .map(|(line, col)| (line + 1, col + 1))
+                .unwrap_or((1, 1));
+            diagnostics.push(Diagnostic::error(
+                path,
+                position,
+                format!("Invalid TOML: |{}|", e),
+            ));
+            return diagnostics;
+        }
+    };
+    let positions = get_positions(content);
+    let position_of = |key: &str| *positions.get(key).unwrap_or(&(1, 1));
+
+    for key in table.keys() {
+        if !THEME_FIELDS.contains(&key.as_str()) {
+            diagnostics.push(Diagnostic::warning(
+                path,
+                position_of(key),
+                format!("Unknown field |{}| will be ignored", key),
+            ));
+        }
+    }
+
+    match table.get("name") {
+        Some(Value::String(name)) => check_name(
+            theme_file,
+            name,
+            position_of("name"),
+            all_themes,
+            &mut diagnostics,
+        ),
+        Some(_) => diagnostics.push(Diagnostic::error(
+            path,
+            position_of("name"),
+            "Field |name| must be a string".to_string(),
+        )),
+        None => diagnostics.push(Diagnostic::error(
+            path,
+            (1, 1),
+            "Missing field |name|".to_string(),
+        )),
+    }
+
+    let inherited_colors = match table.get("inherits") {
+        Some(Value::String(parent)) => match get_inherited_colors(theme_file, parent, all_themes) {
+            Ok(inherited_colors) => inherited_colors,
+            Err(message) => {
+                diagnostics.push(Diagnostic::error(path, position_of("inherits"), message));
+                BTreeSet::new()
+            }
+        },
+        Some(_) => {
+            diagnostics.push(Diagnostic::error(
+                path,
+                position_of("inherits"),
+                "Field |inherits| must be the name of a theme".to_string(),
+            ));
+            BTreeSet::new()
+        }
+        None => BTreeSet::new(),
+    };
+
+    for field in ["light", "dark"] {
+        match table.get(field) {
+            Some(Value::String(counterpart)) => {
+                let exists = all_themes
+                    .iter()
+                    .any(|theme| theme.get_name().to_lowercase() == counterpart.to_lowercase());
+                if !exists {
+                    diagnostics.push(Diagnostic::error(
+                        path,
+                        position_of(field),
+                        format!("The {} counterpart |{}| does not exist", field, counterpart),
+                    ));
+                }
+            }
+            Some(_) => diagnostics.push(Diagnostic::error(
+                path,
+                position_of(field),
+                format!("Field |{}| must be the name of a theme", field),
+            )),
+            None => {}
+        }
+    }
+
+    match table.get("colors") {
+        Some(Value::Table(colors)) => check_colors(
+            path,
+            colors,
+            &inherited_colors,
+            &position_of,
+            &mut diagnostics,
+        ),
+        Some(_) => diagnostics.push(Diagnostic::error(
+            path,
+            position_of("colors"),
+            "Field |colors| must be a table".to_string(),
+        )),
+        None => check_colors(
+            path,
+            &toml::value::Table::new(),
+            &inherited_colors,
+            &position_of,
+            &mut diagnostics,
+        ),
+    }
+
+    match table.get("extras") {
+        Some(Value::Table(extras)) => {
+            check_extras(path, extras, desktop, &position_of, &mut diagnostics)
+        }
+        Some(_) => diagnostics.push(Diagnostic::error(
+            path,
+            position_of("extras"),
+            "Field |extras| must be a table".to_string(),
+        )),
+        None => (),
+    }
+
+    // Anything the checks above missed would still make the theme fall back to default colors
+    if diagnostics.iter().all(|diagnostic| !diagnostic.is_error()) {
+        if let Err(e) = toml::from_str::<Theme>(content) {
+            diagnostics.push(Diagnostic::error(
+                path,
+                (1, 1),
+                format!("Invalid theme: |{}|", e),
+            ));
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.get_line(), diagnostic.get_col()));
+    diagnostics
+}
+
+fn check_name(
+    theme_file: &ThemeFile,
+    name: &str,
+    position: (usize, usize),
+    all_themes: &[ThemeFile],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let path = theme_file.get_path();
+    let file_name = theme_file.get_name();
+
+    if name != file_name {
+        diagnostics.push(Diagnostic::warning(
+            path,
+            position,
+            format!(
+                "Theme name |{}| does not match its file name, it is listed as |{}|",
+                name, file_name
+            ),
+        ));
+    }
+
+    for other in all_themes.iter().filter(|other| other.get_path() != path) {
+        if other.get_name().to_lowercase() == file_name.to_lowercase() {
+            diagnostics.push(Diagnostic::error(
+                path,
+                (1, 1),
+                format!(
+                    "Duplicated theme |{}|, also defined in |{}|",
+                    file_name,
+                    other.get_path()
+                ),
+            ));
+        } else if other.get_name().to_lowercase() == name.to_lowercase() {
+            diagnostics.push(Diagnostic::error(
+                path,
+                position,
+                format!(
+                    "Theme name |{}| is already used by |{}|",
+                    name,
+                    other.get_path()
+                ),
+            ));
+        }
+    }
+}
+
+// Colors defined by the ancestors of a theme, or why they cannot be resolved
+fn get_inherited_colors(
+    theme_file: &ThemeFile,
+    parent: &str,
+    all_themes: &[ThemeFile],
+) -> Result<BTreeSet<String>, String> {
+    let mut chain = vec![theme_file.get_name().clone()];
+    let mut inherited_colors = BTreeSet::new();
+    let mut next = Some(parent.to_string());
+
+    while let Some(parent) = next {
+        let parent_file = all_themes
+            .iter()
+            .find(|other| other.get_name().to_lowercase() == parent.to_lowercase())
+            .ok_or(format!("Parent theme |{}| does not exist", parent))?;
+        if chain
+            .iter()
+            .any(|name| name.to_lowercase() == parent_file.get_name().to_lowercase())
+        {
+            return Err(format!(
+                "Inheritance cycle |{} -> {}|",
+                chain.join(" -> "),
+                parent_file.get_name()
+            ));
+        }
+        chain.push(parent_file.get_name().clone());
+
+        let table = fs::read_to_string(parent_file.get_path())
+            .ok()
+            .and_then(|content| content.parse::<Value>().ok())
+            .ok_or(format!(
+                "Parent theme |{}| could not be read, run |gtheme theme check {}|",
+                parent_file.get_name(),
+                parent_file.get_name()
+            ))?;
+        if let Some(Value::Table(colors)) = table.get("colors") {
+            inherited_colors.extend(colors.keys().cloned());
+        }
+        next = table
+            .get("inherits")
+            .and_then(|inherits| inherits.as_str())
+            .map(|inherits| inherits.to_string());
+    }
+    Ok(inherited_colors)
+}
+
+fn check_colors(
+    path: &str,
+    colors: &toml::value::Table,
+    inherited_colors: &BTreeSet<String>,
+    position_of: &dyn Fn(&str) -> (usize, usize),
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let missing: Vec<&str> = COLOR_KEYS
+        .into_iter()
+        .filter(|key| !colors.contains_key(*key) && !inherited_colors.contains(*key))
+        .collect();
+    if !missing.is_empty() {
+        diagnostics.push(Diagnostic::error(
+            path,
+            position_of("colors"),
+            format!("Missing colors |{}|", missing.join(", ")),
+        ));
+    }
+
+    for (key, value) in colors {
+        let position = position_of(&format!("colors.{}", key));
+        let color = match value {
+            Value::String(color) => color,
+            _ => {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    position,
+                    format!("Color |{}| must be a string", key),
+                ));
+                continue;
+            }
+        };
+        if color.is_empty() {
+            diagnostics.push(Diagnostic::error(
+                path,
+                position,
+                format!("Color |{}| is empty", key),
+            ));
+        } else if color.starts_with('#') && is_valid_hex(&color[1..]) {
+            diagnostics.push(Diagnostic::error(
+                path,
+                position,
+                format!(
+                    "Color |{}| must not start with '#', use |{}|",
+                    key,
+                    &color[1..]
+                ),
+            ));
+        } else if !is_valid_hex(color) {
+            diagnostics.push(Diagnostic::error(
+                path,
+                position,
+                format!(
+                    "Invalid hexadecimal color |{}| in |{}|, expected 6 hex digits such as |2e3440|",
+                    color, key
+                ),
+            ));
+        }
+    }
+}
+
+fn check_extras(
+    path: &str,
+    extras: &toml::value::Table,
+    desktop: Option<&Desktop>,
+    position_of: &dyn Fn(&str) -> (usize, usize),
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (key, value) in extras {
+        let position = position_of(&format!("extras.{}", key));
+        let is_valid = match value {
+            Value::Array(args) => args.iter().all(|arg| arg.is_str()),
+            _ => false,
+        };
+        if !is_valid {
+            diagnostics.push(Diagnostic::error(
+                path,
+                position,
+                format!("Extra |{}| must be a list of strings", key),
+            ));
+        }
+
+        if let Some(desktop) = desktop {
+            if !desktop
+                .get_extras()
+                .iter()
+                .any(|extra| extra.get_name() == key)
+            {
+                diagnostics.push(Diagnostic::warning(
+                    path,
+                    position,
+                    format!(
+                        "Unknown extra |{}|, desktop |{}| does not provide it",
+                        key,
+                        desktop.get_name()
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_content(content: &str) -> Vec<Diagnostic> {
+        let theme_file = ThemeFile {
+            name: "Test".to_string(),
+            path: "Test.toml".to_string(),
+        };
+        check(&theme_file, content, &[], None)
+    }
+
+    fn get_colors(skip: &str) -> String {
+        COLOR_KEYS
+            .iter()
+            .filter(|key| **key != skip)
+            .map(|key| format!("{} = \"2e3440\"\n", key))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_theme() {
+        let content = format!("name = \"Test\"\n[extras]\n[colors]\n{}", get_colors(""));
+        assert!(check_content(&content).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_theme() {
+        let content = format!(
+            "name = \"Other\"\n[extras]\nwallpaper = 3\n[colors]\n{}red = \"#bf616a\"\n",
+            get_colors("red")
+        );
+        let diagnostics = check_content(&content);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.is_error(), d.get_line(), d.get_col()))
+            .collect();
+        assert_eq!(found, vec![(false, 1, 1), (true, 3, 1), (true, 25, 1)]);
+
+        let diagnostics = check_content("name = \"Test\"\n[colors]\nred = \"zz\"\n");
+        assert!(diagnostics.iter().any(|d| d.get_line() == 3));
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.get_message().contains("Missing colors")));
+
+        // Parent themes are looked up in the installed themes, there are none here
+        let diagnostics = check_content("name = \"Test\"\ninherits = \"Nord\"\n");
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.get_line() == 2 && d.is_error()));
+
+        let diagnostics = check_content("name = \"Test\"\nlight = \"Nord Light\"\n");
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.get_line() == 2 && d.get_message().contains("light counterpart")));
+
+        let diagnostics = check_content("name = \"Test\"\n[colors\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].get_line(), 2);
+    }
+}
@@ -0,0 +1,270 @@
+use log::{error, info, warn};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core;
+
+#[derive(Debug)]
+struct StagedFile {
+    target: PathBuf,
+    temp: PathBuf,
+    content: String,
+    // None means the target did not exist before the transaction
+    snapshot: Option<Vec<u8>>,
+    // Call to `stage` that last wrote the content
+    stage: usize,
+}
+
+impl StagedFile {
+    fn is_changed(&self) -> bool {
+        self.snapshot.as_deref() != Some(self.content.as_bytes())
+    }
+}
+
+// Groups every output written by a theme apply so they are either all changed or none.
+// Contents are staged in memory, written to temporary files on commit and then renamed
+// over their targets. If anything fails, previous contents are restored from the snapshots.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    staged: Vec<StagedFile>,
+    // Calls to `stage`, including those overwriting an already staged output
+    stage_count: usize,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Transaction {
+            staged: Vec::new(),
+            stage_count: 0,
+        }
+    }
+
+    pub fn get_snapshots(&self) -> Vec<(&Path, &Option<Vec<u8>>)> {
+        self.staged
+            .iter()
+            .map(|file| (file.target.as_path(), &file.snapshot))
+            .collect()
+    }
+
+    pub fn get_staged_count(&self) -> usize {
+        self.staged.len()
+    }
+
+    pub fn get_stage_count(&self) -> usize {
+        self.stage_count
+    }
+
+    // Whether any output staged after the first `from` calls to `stage` differs from the
+    // file on disk
+    pub fn has_changes_since(&self, from: usize) -> bool {
+        self.staged
+            .iter()
+            .any(|file| file.stage >= from && file.is_changed())
+    }
+
+    // Unstages outputs whose content is already on disk, so they are left untouched
+    pub fn discard_unchanged(&mut self) {
+        let staged_count = self.staged.len();
+        self.staged.retain(|file| file.is_changed());
+        let unchanged = staged_count - self.staged.len();
+        if unchanged > 0 {
+            info!("Skipping |{}| unchanged output files", unchanged);
+        }
+    }
+
+    pub fn stage(&mut self, output_path: &str, content: String) -> bool {
+        let target = Self::resolve_target(Path::new(output_path));
+        let stage = self.stage_count;
+        self.stage_count += 1;
+
+        // If two patterns write the same output, the last one wins but the snapshot is kept
+        if let Some(staged) = self.staged.iter_mut().find(|file| file.target == target) {
+            warn!(
+                "Output |{}| was already written by another pattern, overwriting it",
+                target.display()
+            );
+            staged.content = content;
+            staged.stage = stage;
+            return true;
+        }
+
+        let snapshot = match fs::read(&target) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                error!("Could not snapshot |{}|: |{}|", target.display(), e);
+                return false;
+            }
+        };
+
+        let temp = Self::get_temp_path(&target);
+        self.staged.push(StagedFile {
+            target,
+            temp,
+            content,
+            snapshot,
+            stage,
+        });
+        true
+    }
+
+    pub fn commit(self) -> bool {
+        // First write every temporary file, so a failure here leaves targets untouched
+        for (idx, file) in self.staged.iter().enumerate() {
+            if let Err(e) = core::write_content_to(&file.content, &file.temp) {
+                error!("Could not create |{}|: |{}|", file.temp.display(), e);
+                Self::remove_temps(&self.staged[..=idx]);
+                return false;
+            }
+            // Keep permissions of the file being replaced (i.e, executable scripts)
+            if let Ok(md) = fs::metadata(&file.target) {
+                let _ = fs::set_permissions(&file.temp, md.permissions());
+            }
+        }
+
+        for (idx, file) in self.staged.iter().enumerate() {
+            if let Err(e) = fs::rename(&file.temp, &file.target) {
+                error!(
+                    "Could not replace |{}|, rolling back: |{}|",
+                    file.target.display(),
+                    e
+                );
+                Self::remove_temps(&self.staged[idx..]);
+                Self::restore(&self.staged[..idx]);
+                return false;
+            }
+        }
+        info!("Successfully written |{}| output files", self.staged.len());
+        true
+    }
+
+    pub fn rollback(self) {
+        info!(
+            "Discarding |{}| staged output files, nothing has been changed",
+            self.staged.len()
+        );
+    }
+
+    fn restore(files: &[StagedFile]) {
+        for file in files.iter().rev() {
+            let target_display = file.target.display();
+            let result = match &file.snapshot {
+                Some(bytes) => fs::write(&file.target, bytes),
+                None => fs::remove_file(&file.target),
+            };
+            match result {
+                Ok(_) => info!("Restored |{target_display}|"),
+                Err(e) => error!("Could not restore |{target_display}|: |{e}|"),
+            }
+        }
+    }
+
+    fn remove_temps(files: &[StagedFile]) {
+        for file in files {
+            let _ = fs::remove_file(&file.temp);
+        }
+    }
+
+    fn resolve_target(path: &Path) -> PathBuf {
+        // Write through symlinks as File::create does, instead of replacing the link
+        match fs::symlink_metadata(path) {
+            Ok(md) if md.file_type().is_symlink() => {
+                fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+            }
+            _ => path.to_path_buf(),
+        }
+    }
+
+    fn get_temp_path(target: &Path) -> PathBuf {
+        let file_name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        target.with_file_name(format!(".{}.gtheme-tmp", file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("gtheme-transaction-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_commit_writes_all_outputs() {
+        let dir = test_dir("commit");
+        let existing = dir.join("existing.conf");
+        let new = dir.join("sub/new.conf");
+        fs::write(&existing, "old").unwrap();
+
+        let mut transaction = Transaction::new();
+        assert!(transaction.stage(existing.to_str().unwrap(), "first".to_string()));
+        assert!(transaction.stage(new.to_str().unwrap(), "second".to_string()));
+        assert!(transaction.commit());
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&new).unwrap(), "second");
+        assert!(!dir.join(".existing.conf.gtheme-tmp").exists());
+    }
+
+    #[test]
+    fn test_failed_commit_restores_snapshots() {
+        let dir = test_dir("rollback");
+        let existing = dir.join("existing.conf");
+        fs::write(&existing, "old").unwrap();
+        let blocked = dir.join("blocked");
+
+        let mut transaction = Transaction::new();
+        assert!(transaction.stage(existing.to_str().unwrap(), "new".to_string()));
+        assert!(transaction.stage(blocked.to_str().unwrap(), "new".to_string()));
+        // A non-empty directory created after staging makes the rename fail
+        fs::create_dir_all(blocked.join("not-empty")).unwrap();
+        assert!(!transaction.commit());
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!dir.join(".existing.conf.gtheme-tmp").exists());
+        assert!(!dir.join(".blocked.gtheme-tmp").exists());
+    }
+
+    #[test]
+    fn test_discard_unchanged() {
+        let dir = test_dir("unchanged");
+        let same = dir.join("same.conf");
+        let changed = dir.join("changed.conf");
+        fs::write(&same, "same").unwrap();
+        fs::write(&changed, "old").unwrap();
+
+        let mut transaction = Transaction::new();
+        assert!(transaction.stage(same.to_str().unwrap(), "same".to_string()));
+        assert!(!transaction.has_changes_since(0));
+        assert!(transaction.stage(changed.to_str().unwrap(), "new".to_string()));
+        assert!(transaction.has_changes_since(1));
+
+        transaction.discard_unchanged();
+        assert_eq!(transaction.get_staged_count(), 1);
+        assert_eq!(transaction.get_snapshots()[0].0, changed.as_path());
+    }
+
+    #[test]
+    fn test_changes_of_overwritten_output() {
+        let dir = test_dir("overwritten");
+        let output = dir.join("colors.conf");
+        fs::write(&output, "old").unwrap();
+
+        let mut transaction = Transaction::new();
+        assert!(transaction.stage(output.to_str().unwrap(), "old".to_string()));
+        let stage_count = transaction.get_stage_count();
+        assert!(!transaction.has_changes_since(stage_count));
+        // A second pattern writing the same output
+        assert!(transaction.stage(output.to_str().unwrap(), "new".to_string()));
+        assert!(transaction.has_changes_since(stage_count));
+        assert_eq!(transaction.get_staged_count(), 1);
+    }
+}
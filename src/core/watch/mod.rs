@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// How often watched paths are scanned for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Modification time and size of a file
+type FileState = (Option<SystemTime>, u64);
+
+// Detects created, modified and removed files by polling, so it works the same on every
+// platform and filesystem. Directories are watched recursively, hidden files are ignored
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    snapshot: BTreeMap<PathBuf, FileState>,
+}
+
+impl Watcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let snapshot = scan(&paths);
+        Watcher { paths, snapshot }
+    }
+
+    // Watches other paths from now on. Paths already watched keep their state, so changes
+    // not polled yet are still reported
+    pub fn set_paths(&mut self, paths: Vec<PathBuf>) {
+        let added: Vec<PathBuf> = paths
+            .iter()
+            .filter(|path| !self.paths.contains(path))
+            .cloned()
+            .collect();
+        self.snapshot
+            .retain(|file, _| paths.iter().any(|path| file.starts_with(path)));
+        self.snapshot.extend(scan(&added));
+        self.paths = paths;
+    }
+
+    // Blocks until a file changes, and then until nothing else changes for `debounce`, so
+    // editors saving several files (or saving in several steps) produce a single event
+    pub fn wait_changes(&mut self, debounce: Duration) -> BTreeSet<PathBuf> {
+        let mut changes = BTreeSet::new();
+        let mut last_change = SystemTime::now();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let new_changes = self.poll();
+            if !new_changes.is_empty() {
+                changes.extend(new_changes);
+                last_change = SystemTime::now();
+            } else if !changes.is_empty() && last_change.elapsed().unwrap_or_default() >= debounce {
+                return changes;
+            }
+        }
+    }
+
+    // Files changed since the last poll
+    fn poll(&mut self) -> BTreeSet<PathBuf> {
+        let snapshot = scan(&self.paths);
+        let mut changes: BTreeSet<PathBuf> = snapshot
+            .iter()
+            .filter(|(path, state)| self.snapshot.get(*path) != Some(state))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changes.extend(
+            self.snapshot
+                .keys()
+                .filter(|path| !snapshot.contains_key(*path))
+                .cloned(),
+        );
+        self.snapshot = snapshot;
+        changes
+    }
+}
+
+fn scan(paths: &[PathBuf]) -> BTreeMap<PathBuf, FileState> {
+    let mut snapshot = BTreeMap::new();
+    for path in paths {
+        scan_path(path, &mut snapshot);
+    }
+    snapshot
+}
+
+fn scan_path(path: &Path, snapshot: &mut BTreeMap<PathBuf, FileState>) {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        // Removed while scanning, or not created yet
+        Err(_) => return,
+    };
+    if !metadata.is_dir() {
+        snapshot.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+        return;
+    }
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with('.') {
+            scan_path(&entry.path(), snapshot);
+        }
+    }
+}
+
+// Name of the pattern a changed file belongs to: the pattern file itself, or any file
+// inside a pattern with submodules. Patterns are given by name and path
+pub fn get_pattern_name(patterns: &BTreeMap<String, PathBuf>, path: &Path) -> Option<String> {
+    patterns
+        .iter()
+        .find(|(_, pattern_path)| path.starts_with(pattern_path))
+        .map(|(name, _)| name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_poll() {
+        let dir = env::temp_dir().join("gtheme-watch-test");
+        fs::create_dir_all(dir.join("polybar")).unwrap();
+        let file = dir.join("polybar").join("bar.pattern");
+        fs::write(&file, "a").unwrap();
+        fs::write(dir.join(".hidden"), "a").unwrap();
+
+        let mut watcher = Watcher::new(vec![dir.clone()]);
+        assert!(watcher.poll().is_empty());
+        fs::write(&file, "ab").unwrap();
+        fs::write(dir.join(".hidden"), "ab").unwrap();
+        assert_eq!(watcher.poll(), BTreeSet::from([file.clone()]));
+        fs::remove_file(&file).unwrap();
+        assert_eq!(watcher.poll(), BTreeSet::from([file]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_set_paths() {
+        let dir = env::temp_dir().join("gtheme-watch-paths-test");
+        fs::create_dir_all(&dir).unwrap();
+        let (child, parent) = (dir.join("Child.toml"), dir.join("Parent.toml"));
+        fs::write(&child, "a").unwrap();
+        fs::write(&parent, "a").unwrap();
+
+        let mut watcher = Watcher::new(vec![child.clone()]);
+        fs::write(&child, "ab").unwrap();
+        watcher.set_paths(vec![child.clone(), parent.clone()]);
+        assert_eq!(watcher.poll(), BTreeSet::from([child.clone()]));
+        fs::write(&parent, "ab").unwrap();
+        assert_eq!(watcher.poll(), BTreeSet::from([parent.clone()]));
+
+        watcher.set_paths(vec![child]);
+        fs::write(&parent, "abc").unwrap();
+        assert!(watcher.poll().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_pattern_name() {
+        let dir = Path::new("/desktop/gtheme/patterns");
+        let patterns = BTreeMap::from([
+            ("kitty".to_string(), dir.join("kitty.pattern")),
+            ("polybar".to_string(), dir.join("polybar")),
+        ]);
+        let name = |path: &str| get_pattern_name(&patterns, Path::new(path));
+        assert_eq!(
+            name("/desktop/gtheme/patterns/kitty.pattern"),
+            Some("kitty".to_string())
+        );
+        assert_eq!(
+            name("/desktop/gtheme/patterns/polybar/modules/cpu.pattern"),
+            Some("polybar".to_string())
+        );
+        assert_eq!(name("/desktop/gtheme/patterns/kitty.pattern.bak"), None);
+        assert_eq!(name("/desktop/gtheme/patterns/notes.txt"), None);
+    }
+}
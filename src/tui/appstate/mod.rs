@@ -4,6 +4,7 @@ use tui::style::Color;
 use crate::core::{
     config::{DesktopConfig, GlobalConfig},
     desktop::Desktop,
+    history::History,
     pattern::Pattern,
     postscript::PostScript,
     theme::Theme,
@@ -22,6 +23,7 @@ pub enum Popup {
     Help,
     Extras,
     Info,
+    History,
 }
 
 pub struct AppState {
@@ -148,6 +150,7 @@ impl AppState {
         popups.insert(Popup::Extras, Self::create_extras_list(global_config));
         popups.insert(Popup::Help, Self::create_help_list());
         popups.insert(Popup::Info, StatefulList::with_items(vec![]));
+        popups.insert(Popup::History, Self::create_history_list());
         popups
     }
 
@@ -161,6 +164,27 @@ impl AppState {
             .title("HELP ﬤ")
     }
 
+    // Newest entries first
+    pub fn create_history_list() -> StatefulList<ScreenItem> {
+        let entries = History::get_entries()
+            .into_iter()
+            .rev()
+            .map(|entry| {
+                let label = format!(
+                    "{}  {} ({})",
+                    entry.get_date(),
+                    entry.get_theme(),
+                    entry.get_desktop()
+                );
+                ScreenItem::History(entry, label)
+            })
+            .collect();
+
+        StatefulList::with_items(entries)
+            .color(Color::Blue)
+            .title("HISTORY ")
+    }
+
     fn create_extras_list(global_config: &GlobalConfig) -> StatefulList<ScreenItem> {
         let extras = match global_config.get_current_desktop() {
             None => vec![],
@@ -128,6 +128,28 @@ fn manage_input(
                     }
                 }
             }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                let history_list = popups.get_mut(&Popup::History).unwrap();
+                match current_popup {
+                    Some(Popup::History) => {
+                        lists[LEFT_LIST].next();
+                        history_list.unselect();
+                        *current_popup = None
+                    }
+                    Some(_) => {}
+                    None => {
+                        // Entries may have changed since the last time, so it is always rebuilt
+                        *history_list = AppState::create_history_list();
+                        lists[LEFT_LIST].unselect();
+                        lists[RIGHT_LIST].unselect();
+                        history_list.next();
+                        *current_popup = Some(Popup::History)
+                    }
+                }
+            }
+            KeyCode::Char('u') | KeyCode::Char('U') if current_popup.is_none() => {
+                ScreenItem::undo(global_config, desktop_config)
+            }
             KeyCode::Esc => match current_popup {
                 Some(p) => {
                     let popup_list = popups.get_mut(p).unwrap();
@@ -175,9 +197,15 @@ fn manage_input(
             }
             KeyCode::Enter => match current_popup {
                 Some(p) => {
-                    if let Some(i) = popups.get_mut(p).unwrap().get_selected() {
+                    let popup_list = popups.get_mut(p).unwrap();
+                    if let Some(i) = popup_list.get_selected() {
                         i.apply(global_config, desktop_config)
                     }
+                    // Restoring an entry removes the newer ones
+                    if *p == Popup::History {
+                        *popup_list = AppState::create_history_list();
+                        popup_list.next();
+                    }
                 }
                 None => {
                     if let Some(i) = lists[current_list].get_selected() {
@@ -215,9 +243,9 @@ fn manage_input(
                     };
                     match item {
                         ScreenItem::Pattern(_) => item.invert(desktop_config),
-                        ScreenItem::Desktop(_) => {
+                        ScreenItem::Desktop(_) | ScreenItem::Theme(_) => {
                             let info_list = popups.get_mut(&Popup::Info).unwrap();
-                            item.create_desktop_info(info_list);
+                            item.create_info(info_list);
 
                             lists[LEFT_LIST].unselect();
                             lists[RIGHT_LIST].unselect();
@@ -361,6 +389,19 @@ fn draw_ui(f: &mut Frame<CrosstermBackend<io::Stdout>>, app_state: &mut AppState
         );
     }
 
+    // History popup
+    if *current_popup == Some(Popup::History) {
+        let history_list = popups.get_mut(&Popup::History).unwrap();
+        let history_widget = ListWidget::new(history_list, global_config, desktop_config);
+        let area = centered_rect(60, 70, f.size());
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(
+            history_widget.get_widget(),
+            area,
+            history_list.get_mut_state(),
+        );
+    }
+
     // Info popup
     if *current_popup == Some(Popup::Info) {
         let info_list = popups.get_mut(&Popup::Info).unwrap();
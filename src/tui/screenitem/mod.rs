@@ -8,7 +8,9 @@ use tui::style::Color;
 
 use crate::core::{
     config::{DesktopConfig, DesktopInfo, GlobalConfig},
+    dependency::Dependency,
     desktop::DesktopFile,
+    history::{History, HistoryEntry},
     pattern::PatternFile,
     postscript::PostScript,
     theme::ThemeFile,
@@ -21,6 +23,8 @@ pub enum ScreenItem {
     Theme(ThemeFile),
     Pattern(PatternFile),
     Extra(PostScript),
+    // History entry with the label shown in the list
+    History(HistoryEntry, String),
     Help(String),
     Info(String),
 }
@@ -31,6 +35,7 @@ impl ScreenItem {
             ScreenItem::Theme(t) => t.get_name(),
             ScreenItem::Pattern(p) => p.get_name(),
             ScreenItem::Extra(e) => e.get_name(),
+            ScreenItem::History(_, label) => label,
             ScreenItem::Help(s) => &s,
             ScreenItem::Info(s) => &s,
         }
@@ -42,6 +47,7 @@ impl ScreenItem {
             ScreenItem::Theme(t) => Some(t.get_path().clone()),
             ScreenItem::Pattern(p) => Some(p.get_path().clone()),
             ScreenItem::Extra(e) => Some(e.get_path().clone()),
+            ScreenItem::History(_, _) => None,
             ScreenItem::Help(_) => None,
             ScreenItem::Info(_) => None,
         }
@@ -175,6 +181,9 @@ impl ScreenItem {
             ScreenItem::Theme(t) => Self::apply_theme(t, global_config, desktop_config),
             ScreenItem::Pattern(_) => Self::toggle_active(self, desktop_config),
             ScreenItem::Extra(_) => Self::toggle_active(self, desktop_config),
+            ScreenItem::History(entry, _) => {
+                Self::restore_history(entry, global_config, desktop_config)
+            }
             ScreenItem::Help(_) | ScreenItem::Info(_) => (),
         }
     }
@@ -211,7 +220,7 @@ impl ScreenItem {
         }
     }
 
-    pub fn create_desktop_info(&self, stateful_list: &mut StatefulList<ScreenItem>) {
+    pub fn create_info(&self, stateful_list: &mut StatefulList<ScreenItem>) {
         match self {
             ScreenItem::Desktop(d) => {
                 let desktop_info = DesktopInfo::new(&d);
@@ -236,6 +245,36 @@ impl ScreenItem {
                     .color(Color::Green)
                     .title("INFO ")
             }
+            ScreenItem::Theme(t) => {
+                let theme = t.to_theme();
+
+                let mut lines: Vec<ScreenItem> = vec![];
+                lines.push(ScreenItem::Info(format!("Name: {}", theme.get_name())));
+                if let Some(parent) = theme.get_inherits() {
+                    lines.push(ScreenItem::Info(format!("Inherits: {}", parent)));
+                }
+                lines.push(ScreenItem::Info("Colors:".to_string()));
+                for (key, value) in theme.get_colors() {
+                    lines.push(ScreenItem::Info(format!(
+                        "- {:<22} #{:<8} {}",
+                        key,
+                        value,
+                        theme.get_color_origin(key)
+                    )));
+                }
+                lines.push(ScreenItem::Info("Extras:".to_string()));
+                for (extra, args) in theme.get_extras() {
+                    lines.push(ScreenItem::Info(format!(
+                        "- {:<22} {} ({})",
+                        extra,
+                        args.join(" "),
+                        theme.get_extra_origin(extra)
+                    )));
+                }
+                *stateful_list = StatefulList::with_items(lines)
+                    .color(Color::Green)
+                    .title("INFO ")
+            }
             _ => (),
         }
     }
@@ -289,7 +328,7 @@ impl ScreenItem {
                 Some(d_config) => *d_config.get_actived().get(e.get_name()).unwrap_or(&false),
                 None => false,
             },
-            ScreenItem::Help(_) | ScreenItem::Info(_) => false,
+            ScreenItem::History(_, _) | ScreenItem::Help(_) | ScreenItem::Info(_) => false,
         }
     }
 
@@ -330,18 +369,61 @@ impl ScreenItem {
             }
         };
 
-        current_desktop.apply_theme(
+        if !current_desktop.apply_theme(
             &theme.to_theme(),
             desktop_config.get_actived(),
             desktop_config.get_inverted(),
             false,
-        );
+            false,
+            false,
+        ) {
+            return;
+        }
 
         *global_config.get_mut_current_theme() = Some(theme.clone());
         global_config.save()
     }
 
+    fn restore_history(
+        entry: &HistoryEntry,
+        global_config: &mut GlobalConfig,
+        desktop_config: &mut Option<DesktopConfig>,
+    ) {
+        if History::restore(entry.get_id(), global_config) {
+            global_config.save();
+            Self::reload_desktop_config(desktop_config);
+        }
+    }
+
+    pub fn undo(global_config: &mut GlobalConfig, desktop_config: &mut Option<DesktopConfig>) {
+        if History::undo(global_config) {
+            global_config.save();
+            Self::reload_desktop_config(desktop_config);
+        }
+    }
+
+    // History restores the patterns of the desktop config, which is saved again on exit
+    fn reload_desktop_config(desktop_config: &mut Option<DesktopConfig>) {
+        if let Some(config) = desktop_config {
+            *config = DesktopConfig::new(&config.get_desktop().clone());
+        }
+    }
+
     fn apply_desktop(next_desktop: &DesktopFile, global_config: &mut GlobalConfig) {
+        let missing = Dependency::get_missing(next_desktop);
+        if !missing.is_empty() {
+            let names: Vec<String> = missing
+                .iter()
+                .map(|dependency| format!("{}:{}", dependency.get_kind(), dependency.get_name()))
+                .collect();
+            error!(
+                "Desktop |{}| has missing dependencies: |{}|",
+                next_desktop.get_name(),
+                names.join(", ")
+            );
+            return;
+        }
+
         let current_desktop = match global_config.get_current_desktop() {
             Some(d) => Some(d.to_desktop()),
             None => None,
@@ -359,16 +441,17 @@ impl ScreenItem {
             }
         };
 
-        *global_config.get_mut_current_desktop() = Some(next_desktop.clone());
-        *global_config.get_mut_current_theme() = Some(theme.clone());
-        global_config.save();
-
-        next_desktop.to_desktop().apply(
+        let installed = next_desktop.to_desktop().apply(
             &current_desktop,
             &theme.to_theme(),
             next_desktop_config.get_actived(),
             next_desktop_config.get_inverted(),
             false,
-        )
+        );
+        if installed {
+            *global_config.get_mut_current_desktop() = Some(next_desktop.clone());
+            *global_config.get_mut_current_theme() = Some(theme.clone());
+            global_config.save();
+        }
     }
 }
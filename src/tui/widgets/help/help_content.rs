@@ -26,6 +26,7 @@ pub const HELP_CONTENT: &str = "•• GLOBAL
 
 'Enter'  #apply theme
 'f'      #flag/unflag theme as favourite
+'i'      #show/hide theme colors and the theme each one comes from
 'e'      #edit theme's file in $EDITOR env var
 'z'      #set theme as default for the current desktop
 
@@ -33,4 +34,10 @@ pub const HELP_CONTENT: &str = "•• GLOBAL
 
 'Enter'  #enable/disable extra
 'o'      #show/hide extras popup
-'e'      #edit extra's file in $EDITOR env var";
+'e'      #edit extra's file in $EDITOR env var
+
+•• HISTORY
+
+'r'      #show/hide history popup
+'Enter'  #restore selected apply, undoing every later one
+'u'      #undo last theme apply";
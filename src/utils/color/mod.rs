@@ -0,0 +1,346 @@
+use hex::decode;
+
+// RGB color with alpha, parsed from the hex strings stored in themes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: f64,
+}
+
+impl Color {
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    // Accepts rrggbb, rrggbbaa and rgb, with or without a leading '#'
+    pub fn from_hex(hex_color: &str) -> Option<Self> {
+        let hex_color = hex_color.trim().trim_start_matches('#');
+        let expanded = match hex_color.len() {
+            3 => hex_color.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => hex_color.to_string(),
+            _ => return None,
+        };
+        let bytes = decode(expanded).ok()?;
+        let a = match bytes.get(3) {
+            Some(alpha) => *alpha as f64 / 255.0,
+            None => 1.0,
+        };
+        Some(Color {
+            r: bytes[0],
+            g: bytes[1],
+            b: bytes[2],
+            a,
+        })
+    }
+
+    pub fn from_hsl(h: f64, s: f64, l: f64, a: f64) -> Self {
+        let s = s.clamp(0.0, 1.0);
+        let l = l.clamp(0.0, 1.0);
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h_prime = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h_prime % 2.0 - 1.0).abs());
+        let (r, g, b) = match h_prime as u8 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = l - c / 2.0;
+        let to_u8 = |value: f64| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color {
+            r: to_u8(r),
+            g: to_u8(g),
+            b: to_u8(b),
+            a: a.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn get_rgb(&self) -> (u8, u8, u8) {
+        (self.r, self.g, self.b)
+    }
+    pub fn get_alpha(&self) -> f64 {
+        self.a
+    }
+
+    // Hue in degrees, saturation and lightness between 0 and 1
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let r = self.r as f64 / 255.0;
+        let g = self.g as f64 / 255.0;
+        let b = self.b as f64 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let l = (max + min) / 2.0;
+
+        if delta == 0.0 {
+            return (0.0, 0.0, l);
+        }
+        let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (h, s, l)
+    }
+
+    // #rrggbb
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+    // #rrggbbaa
+    pub fn to_hexa(&self) -> String {
+        format!("{}{:02x}", self.to_hex(), self.get_alpha_byte())
+    }
+    // 0xAARRGGBB
+    pub fn to_argb(&self) -> String {
+        format!(
+            "0x{:02X}{:02X}{:02X}{:02X}",
+            self.get_alpha_byte(),
+            self.r,
+            self.g,
+            self.b
+        )
+    }
+    // rgb(r, g, b)
+    pub fn to_rgb(&self) -> String {
+        format!("rgb({}, {}, {})", self.r, self.g, self.b)
+    }
+    // rgba(r, g, b, a)
+    pub fn to_rgba(&self) -> String {
+        format!(
+            "rgba({}, {}, {}, {})",
+            self.r,
+            self.g,
+            self.b,
+            Self::format_float(self.a)
+        )
+    }
+    // r,g,b as floats between 0 and 1
+    pub fn to_rgb_floats(&self) -> String {
+        [self.r, self.g, self.b]
+            .iter()
+            .map(|channel| Self::format_float(*channel as f64 / 255.0))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn with_alpha(&self, alpha: f64) -> Self {
+        Color {
+            a: alpha.clamp(0.0, 1.0),
+            ..*self
+        }
+    }
+
+    pub fn lighten(&self, amount: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, l + amount, self.a)
+    }
+    pub fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+    pub fn saturate(&self, amount: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s + amount, l, self.a)
+    }
+    pub fn desaturate(&self, amount: f64) -> Self {
+        self.saturate(-amount)
+    }
+    pub fn complement(&self) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h + 180.0, s, l, self.a)
+    }
+    pub fn invert(&self) -> Self {
+        Color {
+            r: 255 - self.r,
+            g: 255 - self.g,
+            b: 255 - self.b,
+            a: self.a,
+        }
+    }
+
+    // Weight is the proportion of `other` in the result, between 0 and 1
+    pub fn mix(&self, other: &Color, weight: f64) -> Self {
+        let weight = weight.clamp(0.0, 1.0);
+        let blend = |a: u8, b: u8| (a as f64 * (1.0 - weight) + b as f64 * weight).round() as u8;
+        Color {
+            r: blend(self.r, other.r),
+            g: blend(self.g, other.g),
+            b: blend(self.b, other.b),
+            a: self.a * (1.0 - weight) + other.a * weight,
+        }
+    }
+
+    // WCAG relative luminance, between 0 (black) and 1 (white)
+    pub fn luminance(&self) -> f64 {
+        let linear = |channel: u8| {
+            let channel = channel as f64 / 255.0;
+            if channel <= 0.03928 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    // WCAG contrast ratio, between 1 and 21
+    pub fn contrast(&self, other: &Color) -> f64 {
+        let (l1, l2) = (self.luminance(), other.luminance());
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+
+    // Closest color with the same hue and saturation reaching `target` contrast against
+    // `background`, or the most contrasted one if the target cannot be reached
+    pub fn with_contrast(&self, background: &Color, target: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        let mut best = *self;
+        for step in 0..=200 {
+            let offset = step as f64 * 0.005;
+            for lightness in [l + offset, l - offset] {
+                if !(0.0..=1.0).contains(&lightness) {
+                    continue;
+                }
+                let candidate = Color::from_hsl(h, s, lightness, self.a);
+                if candidate.contrast(background) >= target {
+                    return candidate;
+                }
+                if candidate.contrast(background) > best.contrast(background) {
+                    best = candidate;
+                }
+            }
+        }
+        best
+    }
+
+    // Same notation as `original`: keeps the leading '#' if it had one, and adds the
+    // alpha channel only when the color is not opaque
+    pub fn format_like(&self, original: &str) -> String {
+        let hex = if self.a < 1.0 {
+            self.to_hexa()
+        } else {
+            self.to_hex()
+        };
+        if original.trim().starts_with('#') {
+            hex
+        } else {
+            hex[1..].to_string()
+        }
+    }
+
+    fn get_alpha_byte(&self) -> u8 {
+        (self.a * 255.0).round() as u8
+    }
+
+    fn format_float(value: f64) -> String {
+        let formatted = format!("{:.3}", value);
+        let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+        if formatted.is_empty() {
+            "0".to_string()
+        } else {
+            formatted.to_string()
+        }
+    }
+}
+
+// Parses amounts such as `10`, `10%` or `0.1` into a fraction between 0 and 1. Numbers
+// without `%` up to 1 are fractions and the rest percentages, so `0.5` is 50%, `1` is the
+// whole amount and `2` is 2%
+pub fn parse_fraction(amount: &str) -> Option<f64> {
+    let amount = amount.trim();
+    match amount.strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|value| value / 100.0),
+        None => amount.parse::<f64>().ok().map(|value| {
+            if value.abs() > 1.0 {
+                value / 100.0
+            } else {
+                value
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_formats() {
+        let color = Color::from_hex("2e3440").unwrap();
+        assert_eq!(color.get_rgb(), (0x2e, 0x34, 0x40));
+        assert_eq!(color.to_hex(), "#2e3440");
+        assert_eq!(color.to_rgb(), "rgb(46, 52, 64)");
+        assert_eq!(color.with_alpha(0.8).to_argb(), "0xCC2E3440");
+        assert_eq!(color.with_alpha(0.8).to_rgba(), "rgba(46, 52, 64, 0.8)");
+        assert_eq!(Color::from_hex("#ffffff").unwrap().to_rgb_floats(), "1,1,1");
+        assert_eq!(Color::from_hex("#fff").unwrap().to_hex(), "#ffffff");
+        assert_eq!(Color::from_hex("#2e3440cc").unwrap().to_hexa(), "#2e3440cc");
+        assert!(Color::from_hex("#2e34").is_none());
+        assert!(Color::from_hex("zzzzzz").is_none());
+    }
+
+    #[test]
+    fn test_transformations() {
+        let red = Color::from_hex("#ff0000").unwrap();
+        assert_eq!(red.lighten(0.2).to_hex(), "#ff6666");
+        assert_eq!(red.darken(0.2).to_hex(), "#990000");
+        assert_eq!(red.desaturate(1.0).to_hex(), "#808080");
+        assert_eq!(red.complement().to_hex(), "#00ffff");
+        assert_eq!(red.invert().to_hex(), "#00ffff");
+
+        let blue = Color::from_hex("#0000ff").unwrap();
+        assert_eq!(red.mix(&blue, 0.5).to_hex(), "#800080");
+
+        let (h, s, l) = Color::from_hex("#bf616a").unwrap().to_hsl();
+        assert_eq!(Color::from_hsl(h, s, l, 1.0).to_hex(), "#bf616a");
+    }
+
+    #[test]
+    fn test_contrast() {
+        let black = Color::from_rgb(0, 0, 0);
+        let white = Color::from_rgb(255, 255, 255);
+        assert_eq!(black.contrast(&white), 21.0);
+        assert_eq!(white.contrast(&white), 1.0);
+        let ratio = Color::from_hex("d8dee9")
+            .unwrap()
+            .contrast(&Color::from_hex("2e3440").unwrap());
+        assert!((ratio - 9.25).abs() < 0.01);
+
+        let background = Color::from_hex("2e3440").unwrap();
+        let fixed = Color::from_hex("4c566a")
+            .unwrap()
+            .with_contrast(&background, 4.5);
+        assert!(fixed.contrast(&background) >= 4.5);
+        assert!(fixed.contrast(&background) < 4.7);
+        assert_eq!(white.with_contrast(&white, 30.0), black);
+    }
+
+    #[test]
+    fn test_format_like_and_fractions() {
+        let color = Color::from_hex("2e3440").unwrap();
+        assert_eq!(color.format_like("bf616a"), "2e3440");
+        assert_eq!(color.format_like("#bf616a"), "#2e3440");
+        assert_eq!(color.with_alpha(0.5).format_like("#bf616a"), "#2e344080");
+
+        assert_eq!(parse_fraction("10"), Some(0.1));
+        assert_eq!(parse_fraction("10%"), Some(0.1));
+        assert_eq!(parse_fraction("0.8"), Some(0.8));
+        assert_eq!(parse_fraction("0.99"), Some(0.99));
+        assert_eq!(parse_fraction("1"), Some(1.0));
+        assert_eq!(parse_fraction("1.0"), Some(1.0));
+        assert_eq!(parse_fraction("1%"), Some(0.01));
+        assert_eq!(parse_fraction("2"), Some(0.02));
+        assert_eq!(parse_fraction("100"), Some(1.0));
+        assert_eq!(parse_fraction("abc"), None);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod color;
+
 use hex::decode;
 
 pub fn hex_to_rgb(hex_color: &String) -> Option<(u8, u8, u8)> {